@@ -4,7 +4,7 @@ use std::io;
 use std::num::ParseIntError;
 use std::result;
 
-use nom::error::ErrorKind;
+use crate::token::Span;
 
 pub type Result<R> = result::Result<R, Error>;
 
@@ -13,24 +13,104 @@ pub enum Error {
     IoError(io::Error),
     SerializationError(serde_json::Error),
     IntParseError(ParseIntError),
-    TokenParseError(String),
-    ParseError(String, ErrorKind),
+    /// Boxed so that `Result`s, which the evaluator returns at every level of
+    /// recursion, stay small.
+    ReadError(Box<Diagnostic>),
+    /// The input ended inside a form, such as an unclosed list or string.
+    UnexpectedEof(Box<Diagnostic>),
     InvalidTokenOperation(String),
+    UnboundVariable(String),
+    /// A name with no binding in the function namespace, in Lisp-2 mode.
+    UnboundFunction(String),
+    /// A `letrec` variable or internal definition used before its value was
+    /// computed.
+    UninitializedVariable(String),
+    /// A `throw` with no `catch` for its tag.
+    NoCatcher(String),
+    /// A `throw` unwinding to the `catch` at this depth of the interpreter's
+    /// catch stack. The evaluator stops it there, so it never escapes.
+    Throw(usize),
+    /// Evaluation recursed so deeply that it would have overflowed the stack.
+    RecursionLimit,
+    BadSyntax(String),
+    WrongType(String),
+    WrongArity(String),
+    DivisionByZero(String),
+    /// An error evaluating the top-level form read from a span of the source.
+    InForm(Box<Span>, Box<Error>),
 }
 
 use Error::*;
 
+/// A reader error, located in the source it was read from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub file: Option<String>,
+    /// The 1-based line of the problem.
+    pub line: usize,
+    /// The 1-based column of the problem, in characters.
+    pub column: usize,
+    /// The text of the offending line, without its line ending.
+    pub source_line: String,
+}
+
+/// Formats the diagnostic with the offending line and a caret under the
+/// problem, e.g.
+///
+/// ```text
+/// unclosed `(` opened at 3:5
+///  --> fib.lisp:3:5
+///   |
+/// 3 |     (if (< n 2)
+///   |     ^
+/// ```
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let number = self.line.to_string();
+        let gutter = " ".repeat(number.len());
+        // Keep tabs so that the caret lines up with the source line.
+        let indent: String = self
+            .source_line
+            .chars()
+            .take(self.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        writeln!(f, "{}", self.message)?;
+        match self.file {
+            Some(ref file) => writeln!(f, "{}--> {}:{}:{}", gutter, file, self.line, self.column)?,
+            None => writeln!(f, "{}--> {}:{}", gutter, self.line, self.column)?,
+        }
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", number, self.source_line)?;
+        write!(f, "{} | {}^", gutter, indent)
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             IoError(ref err) => err.fmt(f),
             SerializationError(ref err) => err.fmt(f),
             IntParseError(ref err) => err.fmt(f),
-            TokenParseError(ref input) => write!(f, "token parsing error on {:?}", input),
-            ParseError(ref input, ref code) => {
-                write!(f, "parse error in {:?}: code {:?}", input, code)
-            }
+            ReadError(ref diagnostic) | UnexpectedEof(ref diagnostic) => diagnostic.fmt(f),
             InvalidTokenOperation(ref msg) => write!(f, "invalid token operation: {}", msg),
+            UnboundVariable(ref name) => write!(f, "unbound variable: {}", name),
+            UnboundFunction(ref name) => write!(f, "unbound function: {}", name),
+            UninitializedVariable(ref name) => {
+                write!(f, "variable used before initialization: {}", name)
+            }
+            NoCatcher(ref tag) => write!(f, "no catch for tag {}", tag),
+            Throw(_) => write!(f, "throw to a catch that is no longer active"),
+            RecursionLimit => write!(f, "recursion too deep"),
+            BadSyntax(ref msg) => write!(f, "bad syntax: {}", msg),
+            WrongType(ref msg) => write!(f, "wrong type: {}", msg),
+            WrongArity(ref msg) => write!(f, "wrong number of arguments: {}", msg),
+            DivisionByZero(ref name) => write!(f, "division by zero in {}", name),
+            InForm(ref span, ref err) => match span.file {
+                Some(ref file) => write!(f, "{}:{}:{}: {}", file, span.line, span.column, err),
+                None => write!(f, "{}:{}: {}", span.line, span.column, err),
+            },
         }
     }
 }
@@ -54,14 +134,3 @@ impl From<ParseIntError> for Error {
         IntParseError(value)
     }
 }
-
-impl From<nom::Err<nom::error::Error<&str>>> for Error {
-    fn from(value: nom::Err<nom::error::Error<&str>>) -> Self {
-        match value {
-            nom::Err::Incomplete(_) => unreachable!(),
-            nom::Err::Error(ref err) | nom::Err::Failure(ref err) => {
-                ParseError(err.input.to_string(), err.code)
-            }
-        }
-    }
-}
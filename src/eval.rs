@@ -0,0 +1,618 @@
+//! A naive evaluator in the style of chapter 1 of *Lisp in Small Pieces*.
+//!
+//! Programs are evaluated directly from the reader's [`Token`] tree against an
+//! explicit environment, producing runtime [`Value`]s. The special forms are
+//! `quote`, `quasiquote`, `if`, `begin`, `set!`, `lambda`, `define`,
+//! `function`, `flet`, `labels`, `dynamic-let`, `dynamic`, `dynamic-set!`,
+//! `defvar`, `let`, `letrec`, `letrec*`, `catch` and `throw`; everything
+//! else is a variable reference, a self-evaluating atom or an application. The derived forms
+//! `let*`, `cond`, `case`, `and`, `or`, `when`, `unless` and `do` are
+//! rewritten into these as they are evaluated.
+//!
+//! Bodies, of procedures and of the binding forms, may start with internal
+//! definitions, which have `letrec*` semantics. Calls in tail position do
+//! not grow the stack, so loops can be written as recursion.
+//!
+//! The same evaluator runs both as a Lisp-1 and, following chapter 2, as a
+//! Lisp-2; see [`Mode`].
+
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use crate::error::{Error, Result};
+use crate::heap::{Closure, Frame, Heap, Object, Ref};
+use crate::printer::Printed;
+use crate::symbol::{self, Symbol};
+use crate::token::Token;
+use crate::value::Value;
+
+mod derived;
+mod dynamic;
+mod escapes;
+mod functions;
+mod letrec;
+mod primitives;
+mod quasiquote;
+
+/// How many objects may be allocated between automatic collections.
+const COLLECTION_THRESHOLD: usize = 10_000;
+
+/// The stack a thread evaluating code should have. Evaluation recurses, even
+/// for calls in tail position, so this bounds how deep recursion and how
+/// long loops written as recursion can go.
+pub const STACK_SIZE: usize = 256 << 20;
+
+/// How many bytes of stack [`Interpreter::eval`] may use before giving up
+/// with [`Error::RecursionLimit`], rather than overflowing the stack. The
+/// rest of [`STACK_SIZE`] is left for the frames between calls to `eval`.
+const STACK_BUDGET: usize = STACK_SIZE - (16 << 20);
+
+/// Whether functions and variables share a namespace.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// One namespace, as in Scheme. `function`, `flet` and `labels` still
+    /// work, but bind and look up ordinary variables.
+    #[default]
+    Lisp1,
+    /// Separate namespaces, as in Common Lisp. The head of an application is
+    /// looked up only among the functions, which `define (name ...)`, `flet`
+    /// and `labels` bind, and `function` (or `#'`) fetches one as a value.
+    Lisp2,
+}
+
+/// The evaluator's state: the heap, the global and dynamic environments, the
+/// active `catch`es and the values that must survive a garbage collection.
+///
+/// Collections only happen between top-level forms, between the iterations
+/// of a `do` loop and on entry to a new scope, such as a procedure body. Any
+/// value the evaluator holds across a call to [`Interpreter::eval`] must
+/// therefore be pushed onto `roots` first.
+pub struct Interpreter {
+    pub heap: Heap,
+    global: Ref,
+    roots: Vec<Ref>,
+    mode: Mode,
+    /// The dynamic bindings in force, innermost last.
+    dynamic: Vec<(Symbol, Value)>,
+    /// The variables declared special by `defvar`.
+    specials: HashSet<Symbol>,
+    /// The tags of the active `catch`es, innermost last.
+    catchers: Vec<Value>,
+    /// The value being thrown while a `throw` unwinds to its `catch`.
+    thrown: Option<Value>,
+    /// How many calls to [`Interpreter::eval`] are active.
+    depth: usize,
+    /// Where the stack was when the outermost active call to
+    /// [`Interpreter::eval`] started.
+    stack_base: usize,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Interpreter {
+    /// Creates a Lisp-1 interpreter whose global environment contains the
+    /// primitive procedures.
+    pub fn new() -> Self {
+        Self::with_mode(Mode::Lisp1)
+    }
+
+    /// Creates an interpreter in the given mode. In Lisp-2 mode the
+    /// primitives are bound as functions, not variables.
+    pub fn with_mode(mode: Mode) -> Self {
+        let mut heap = Heap::default();
+        let global = heap.alloc(Object::Frame(Frame {
+            bindings: Vec::new(),
+            functions: Vec::new(),
+            parent: None,
+        }));
+        let mut interpreter = Interpreter {
+            heap,
+            global,
+            roots: Vec::new(),
+            mode,
+            dynamic: Vec::new(),
+            specials: HashSet::new(),
+            catchers: Vec::new(),
+            thrown: None,
+            depth: 0,
+            stack_base: 0,
+        };
+        for primitive in primitives::PRIMITIVES {
+            interpreter.define_function(
+                global,
+                Symbol::intern(primitive.name),
+                Value::Primitive(primitive.clone()),
+            );
+        }
+        for (name, primitive) in primitives::DERIVED {
+            interpreter.define_function(global, *name, Value::Primitive(primitive.clone()));
+        }
+        interpreter
+    }
+
+    pub fn global(&self) -> Ref {
+        self.global
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Evaluates a top-level form in the global environment.
+    ///
+    /// The result is not a root, so it may be freed by the next evaluation.
+    pub fn eval_toplevel(&mut self, expr: &Token) -> Result<Value> {
+        self.collect_if_due();
+        let base = self.roots.len();
+        let result = self.eval(expr, self.global);
+        self.roots.truncate(base);
+        result
+    }
+
+    /// Formats `value` as `write` does.
+    pub fn print<'a>(&'a self, value: &'a Value) -> Printed<'a> {
+        value.write(&self.heap)
+    }
+
+    /// Frees every object that is unreachable from the global environment,
+    /// the dynamic bindings, the `catch` tags and the evaluator's roots,
+    /// returning the number of objects freed.
+    pub fn collect(&mut self) -> usize {
+        let dynamic = self.dynamic.iter().map(|(_, value)| value);
+        let values = dynamic.chain(&self.catchers).chain(&self.thrown);
+        let roots = std::iter::once(self.global)
+            .chain(values.filter_map(Value::heap_ref))
+            .chain(self.roots.iter().copied());
+        let roots: Vec<Ref> = roots.collect();
+        self.heap.collect(roots)
+    }
+
+    /// Runs a collection if enough objects have been allocated since the
+    /// last one.
+    fn collect_if_due(&mut self) {
+        if self.heap.stats().allocated >= COLLECTION_THRESHOLD {
+            self.collect();
+        }
+    }
+
+    fn root(&mut self, value: &Value) {
+        self.roots.extend(value.heap_ref());
+    }
+
+    fn frame(&self, env: Ref) -> &Frame {
+        match self.heap.get(env) {
+            Object::Frame(frame) => frame,
+            _ => unreachable!("environment reference to a non-frame"),
+        }
+    }
+
+    fn frame_mut(&mut self, env: Ref) -> &mut Frame {
+        match self.heap.get_mut(env) {
+            Object::Frame(frame) => frame,
+            _ => unreachable!("environment reference to a non-frame"),
+        }
+    }
+
+    fn new_frame(&mut self, bindings: Vec<(Symbol, Value)>, parent: Ref) -> Ref {
+        self.heap.alloc(Object::Frame(Frame {
+            bindings,
+            functions: Vec::new(),
+            parent: Some(parent),
+        }))
+    }
+
+    /// Adds a new frame binding `params` to `values` on top of `env`.
+    /// `params` may be a proper list, a dotted list or a single symbol, as in
+    /// `lambda`.
+    pub fn extend(&mut self, env: Ref, params: &Token, values: Vec<Value>) -> Result<Ref> {
+        let mut bindings = Vec::new();
+        let mut values = values.into_iter();
+        let mut cursor = params;
+        loop {
+            match cursor {
+                Token::Cons { head, tail } => {
+                    let name = symbol(head)?;
+                    let value = values
+                        .next()
+                        .ok_or_else(|| Error::WrongArity("too few arguments".to_string()))?;
+                    bindings.push((name, value));
+                    cursor = tail;
+                }
+                Token::EmptyList | Token::Nil => {
+                    if values.next().is_some() {
+                        return Err(Error::WrongArity("too many arguments".to_string()));
+                    }
+                    break;
+                }
+                Token::Symbol { value: name } => {
+                    let rest = self.heap.list(values.collect());
+                    bindings.push((*name, rest));
+                    break;
+                }
+                _ => {
+                    return Err(Error::BadSyntax(format!(
+                        "invalid parameter list {}",
+                        params
+                    )))
+                }
+            }
+        }
+        Ok(self.new_frame(bindings, env))
+    }
+
+    pub fn lookup(&self, env: Ref, name: Symbol) -> Result<Value> {
+        let mut env = env;
+        loop {
+            let frame = self.frame(env);
+            if let Some((_, value)) = frame.bindings.iter().find(|(n, _)| *n == name) {
+                return letrec::check_assigned(name, value.clone());
+            }
+            match frame.parent {
+                Some(parent) => env = parent,
+                None => return Err(Error::UnboundVariable(name.name().to_string())),
+            }
+        }
+    }
+
+    pub fn update(&mut self, env: Ref, name: Symbol, value: Value) -> Result<()> {
+        let mut env = env;
+        loop {
+            let frame = self.frame_mut(env);
+            if let Some((_, v)) = frame.bindings.iter_mut().find(|(n, _)| *n == name) {
+                *v = value;
+                return Ok(());
+            }
+            match frame.parent {
+                Some(parent) => env = parent,
+                None => return Err(Error::UnboundVariable(name.name().to_string())),
+            }
+        }
+    }
+
+    /// Looks `name` up in the function namespace, which is the variable
+    /// namespace in Lisp-1 mode.
+    pub fn lookup_function(&self, env: Ref, name: Symbol) -> Result<Value> {
+        if self.mode == Mode::Lisp1 {
+            return self.lookup(env, name);
+        }
+        let mut env = env;
+        loop {
+            let frame = self.frame(env);
+            if let Some((_, value)) = frame.functions.iter().find(|(n, _)| *n == name) {
+                return letrec::check_assigned(name, value.clone());
+            }
+            match frame.parent {
+                Some(parent) => env = parent,
+                None => return Err(Error::UnboundFunction(name.name().to_string())),
+            }
+        }
+    }
+
+    /// Returns the bindings of the global environment, variables first and
+    /// then, in Lisp-2 mode, functions.
+    pub fn global_bindings(&self) -> Vec<(Symbol, Value)> {
+        let frame = self.frame(self.global);
+        frame
+            .bindings
+            .iter()
+            .chain(&frame.functions)
+            .cloned()
+            .collect()
+    }
+
+    /// Binds `name` in the innermost frame of `env`, replacing any existing
+    /// binding there.
+    pub fn define(&mut self, env: Ref, name: Symbol, value: Value) {
+        let bindings = &mut self.frame_mut(env).bindings;
+        match bindings.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, v)) => *v = value,
+            None => bindings.push((name, value)),
+        }
+    }
+
+    /// Binds `name` in the function namespace of the innermost frame of
+    /// `env`, or as a variable in Lisp-1 mode.
+    pub fn define_function(&mut self, env: Ref, name: Symbol, value: Value) {
+        if self.mode == Mode::Lisp1 {
+            return self.define(env, name, value);
+        }
+        let functions = &mut self.frame_mut(env).functions;
+        match functions.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, v)) => *v = value,
+            None => functions.push((name, value)),
+        }
+    }
+
+    pub fn eval(&mut self, expr: &Token, env: Ref) -> Result<Value> {
+        self.nested(|interp| {
+            let tail = interp.eval_form(expr, env)?;
+            interp.finish(tail)
+        })
+    }
+
+    /// Evaluates `expr` in tail position, leaving a call there for the
+    /// caller to make.
+    fn eval_tail(&mut self, expr: &Token, env: Ref) -> Result<Tail> {
+        self.nested(|interp| interp.eval_form(expr, env))
+    }
+
+    /// Makes the call that evaluating a form in tail position left, if any.
+    fn finish(&mut self, tail: Tail) -> Result<Value> {
+        match tail {
+            Tail::Value(value) => Ok(value),
+            Tail::Call(function, args) => self.invoke(&function, args),
+        }
+    }
+
+    /// Runs `eval`, one level deeper in the evaluator's recursion.
+    fn nested<T>(&mut self, eval: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        // Measure the stack itself rather than count levels: how much a level
+        // uses depends on the form and on how the crate was built.
+        let position = stack_position();
+        if self.depth == 0 {
+            self.stack_base = position;
+        } else if self.stack_base.abs_diff(position) > STACK_BUDGET {
+            return Err(Error::RecursionLimit);
+        }
+        self.depth += 1;
+        let base = self.roots.len();
+        let result = eval(self);
+        self.depth -= 1;
+        if result.is_err() {
+            // Forms give up their roots on the way out with `?`, so an
+            // error that `catch` stops would otherwise leave them behind.
+            self.roots.truncate(base);
+        }
+        result
+    }
+
+    /// Evaluates `expr`, except for a call in tail position: a procedure
+    /// body, say, or a branch of `if`. That call is left to the caller, so
+    /// that a loop written as a tail call runs in constant stack space.
+    fn eval_form(&mut self, expr: &Token, env: Ref) -> Result<Tail> {
+        let result = match expr {
+            Token::Symbol { value } if self.is_special(*value) => self.lookup_dynamic(*value),
+            Token::Symbol { value } => self.lookup(env, *value),
+            Token::Cons { head, tail } => {
+                if let Token::Symbol { value } = head.as_ref() {
+                    let result = match *value {
+                        symbol::QUOTE => self.heap.alloc_datum(nth(tail, 0)?),
+                        symbol::IF => {
+                            let branch = if self.eval(nth(tail, 0)?, env)?.is_false() {
+                                match nth(tail, 2) {
+                                    Ok(alternative) => alternative,
+                                    Err(_) => return Ok(Tail::Value(Value::unspecified())),
+                                }
+                            } else {
+                                nth(tail, 1)?
+                            };
+                            return self.eval_tail(branch, env);
+                        }
+                        symbol::BEGIN => return self.eprogn_tail(tail, env),
+                        symbol::SET => self.eval_set(tail, env, false),
+                        symbol::LAMBDA => Ok(self.make_function(nth(tail, 0)?, rest(tail)?, env)),
+                        symbol::DEFINE => self.eval_define(tail, env),
+                        symbol::FUNCTION => self.eval_function(nth(tail, 0)?, env),
+                        symbol::FLET => return self.eval_flet(tail, env, false),
+                        symbol::LABELS => return self.eval_flet(tail, env, true),
+                        symbol::DYNAMIC_LET => self.eval_dynamic_let(tail, env),
+                        symbol::DYNAMIC => self.lookup_dynamic(symbol(nth(tail, 0)?)?),
+                        symbol::DYNAMIC_SET => self.eval_set(tail, env, true),
+                        symbol::DEFVAR => self.eval_defvar(tail),
+                        symbol::LET => return self.eval_let(tail, env),
+                        symbol::LETREC => return self.eval_letrec(tail, env, false),
+                        symbol::LETREC_STAR => return self.eval_letrec(tail, env, true),
+                        symbol::LET_STAR
+                        | symbol::COND
+                        | symbol::CASE
+                        | symbol::AND
+                        | symbol::OR
+                        | symbol::WHEN
+                        | symbol::UNLESS => return self.eval_derived(*value, tail, env),
+                        symbol::DO => self.eval_do(tail, env),
+                        symbol::CATCH => self.eval_catch(tail, env),
+                        symbol::THROW => self.eval_throw(tail, env),
+                        symbol::QUASIQUOTE => self.eval_quasiquote(nth(tail, 0)?, env),
+                        symbol::UNQUOTE | symbol::UNQUOTE_SPLICING => {
+                            Err(Error::BadSyntax(format!("{} outside quasiquote", value)))
+                        }
+                        _ => return self.eval_application(head, tail, env),
+                    };
+                    return result.map(Tail::Value);
+                }
+                return self.eval_application(head, tail, env);
+            }
+            Token::Integer { .. }
+            | Token::BigInteger { .. }
+            | Token::Float { .. }
+            | Token::Rational { .. }
+            | Token::String { .. }
+            | Token::Boolean { .. }
+            | Token::Character { .. }
+            | Token::EmptyList
+            | Token::Nil => Value::atom(expr),
+            Token::Vector { .. } => self.heap.alloc_datum(expr),
+            Token::ListStart
+            | Token::ListEnd
+            | Token::Dot
+            | Token::Comment { .. }
+            | Token::BlockComment { .. }
+            | Token::DatumComment { .. } => {
+                Err(Error::BadSyntax(format!("cannot evaluate {}", expr)))
+            }
+        };
+        result.map(Tail::Value)
+    }
+
+    /// Evaluates the function and arguments of `(head . args)`, leaving the
+    /// call itself to the caller.
+    fn eval_application(&mut self, head: &Token, args: &Token, env: Ref) -> Result<Tail> {
+        let base = self.roots.len();
+        let function = match self.mode {
+            Mode::Lisp1 => self.eval(head, env)?,
+            Mode::Lisp2 => self.eval_function(head, env)?,
+        };
+        self.root(&function);
+        let args = self.evlis(args, env)?;
+        self.roots.truncate(base);
+        Ok(Tail::Call(function, args))
+    }
+
+    /// Evaluates each expression of a list in order, returning the last
+    /// value.
+    pub fn eprogn(&mut self, exprs: &Token, env: Ref) -> Result<Value> {
+        let tail = self.eprogn_tail(exprs, env)?;
+        self.finish(tail)
+    }
+
+    /// Evaluates each expression of a list in order, the last in tail
+    /// position.
+    fn eprogn_tail(&mut self, exprs: &Token, env: Ref) -> Result<Tail> {
+        let mut cursor = exprs;
+        while let Token::Cons { head, tail } = cursor {
+            if !tail.is_cons() {
+                return self.eval_tail(head, env);
+            }
+            self.eval(head, env)?;
+            cursor = tail;
+        }
+        Ok(Tail::Value(Value::unspecified()))
+    }
+
+    /// Evaluates each expression of a list, returning their values.
+    pub fn evlis(&mut self, exprs: &Token, env: Ref) -> Result<Vec<Value>> {
+        let base = self.roots.len();
+        let mut values = Vec::new();
+        let mut cursor = exprs;
+        while let Token::Cons { head, tail } = cursor {
+            let value = self.eval(head, env)?;
+            self.root(&value);
+            values.push(value);
+            cursor = tail;
+        }
+        self.roots.truncate(base);
+        Ok(values)
+    }
+
+    /// Calls `function` with `args`, and then the function each call leaves
+    /// in tail position in turn, until one returns a value.
+    pub fn invoke(&mut self, function: &Value, args: Vec<Value>) -> Result<Value> {
+        let mut call = (function.clone(), args);
+        loop {
+            let (function, args) = call;
+            let (params, body, env) = match function {
+                Value::Procedure(r) => match self.heap.get(r) {
+                    Object::Closure(closure) => (
+                        Rc::clone(&closure.params),
+                        Rc::clone(&closure.body),
+                        closure.env,
+                    ),
+                    _ => unreachable!("procedure reference to a non-closure"),
+                },
+                Value::Primitive(primitive) => return (primitive.func)(self, args),
+                other => {
+                    return Err(Error::WrongType(format!(
+                        "{} is not a function",
+                        self.print(&other)
+                    )))
+                }
+            };
+            let env = self.extend(env, &params, args)?;
+            call = match self.eval_scope(&body, env)? {
+                Tail::Value(value) => return Ok(value),
+                Tail::Call(function, args) => (function, args),
+            };
+        }
+    }
+
+    fn make_function(&mut self, params: &Token, body: &Token, env: Ref) -> Value {
+        Value::Procedure(self.heap.alloc(Object::Closure(Closure {
+            params: Rc::new(params.clone()),
+            body: Rc::new(body.clone()),
+            env,
+        })))
+    }
+
+    /// Handles `(set! name expr)`, or `(dynamic-set! name expr)` if
+    /// `dynamic` is set.
+    fn eval_set(&mut self, form: &Token, env: Ref, dynamic: bool) -> Result<Value> {
+        let name = symbol(nth(form, 0)?)?;
+        let value = self.eval(nth(form, 1)?, env)?;
+        if dynamic || self.is_special(name) {
+            self.update_dynamic(name, value.clone())?;
+        } else {
+            self.update(env, name, value.clone())?;
+        }
+        Ok(value)
+    }
+
+    /// Handles both `(define name expr)` and `(define (name . params)
+    /// body...)`. In Lisp-2 mode the second binds a function.
+    fn eval_define(&mut self, form: &Token, env: Ref) -> Result<Value> {
+        let target = nth(form, 0)?;
+        let name = match target {
+            Token::Cons { head, tail } => {
+                let name = symbol(head)?;
+                let function = self.make_function(tail, rest(form)?, env);
+                self.define_function(env, name, function);
+                name
+            }
+            _ => {
+                let name = symbol(target)?;
+                let value = self.eval(nth(form, 1)?, env)?;
+                self.define(env, name, value);
+                name
+            }
+        };
+        Ok(Value::Symbol(name))
+    }
+}
+
+/// What is left of evaluating a form in tail position: either nothing, or
+/// a call to make.
+///
+/// The values of a call are not roots, so the caller must make it before
+/// anything can collect garbage.
+enum Tail {
+    Value(Value),
+    Call(Value, Vec<Value>),
+}
+
+/// The address of a local variable, which shows how far the stack has grown.
+#[inline(always)]
+fn stack_position() -> usize {
+    let marker = 0u8;
+    std::hint::black_box(&marker) as *const u8 as usize
+}
+
+fn nth(list: &Token, index: usize) -> Result<&Token> {
+    let mut cursor = list;
+    for _ in 0..index {
+        cursor = rest(cursor)?;
+    }
+    match cursor {
+        Token::Cons { head, .. } => Ok(head),
+        _ => Err(Error::BadSyntax(format!("missing form in {}", list))),
+    }
+}
+
+fn rest(list: &Token) -> Result<&Token> {
+    match list {
+        Token::Cons { tail, .. } => Ok(tail),
+        _ => Err(Error::BadSyntax(format!("missing form in {}", list))),
+    }
+}
+
+fn symbol(token: &Token) -> Result<Symbol> {
+    match token {
+        Token::Symbol { value } => Ok(*value),
+        _ => Err(Error::BadSyntax(format!("{} is not a symbol", token))),
+    }
+}
+
+#[cfg(test)]
+mod tests;
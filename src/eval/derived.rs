@@ -0,0 +1,240 @@
+//! The derived special forms `let*`, `cond`, `case`, `and`, `or`, `when` and
+//! `unless`, rewritten into core forms.
+//!
+//! Each form is expanded one level at a time, when it is evaluated: the
+//! expansion of `(or a b c)` contains `(or b c)`, which is only expanded if
+//! `a` turns out to be false.
+
+use crate::error::{Error, Result};
+use crate::heap::Ref;
+use crate::symbol::{self, Symbol};
+use crate::token::Token;
+
+use super::{nth, rest, Interpreter, Tail};
+
+impl Interpreter {
+    /// Evaluates `(keyword . form)`, where `keyword` names a derived form.
+    pub(super) fn eval_derived(&mut self, keyword: Symbol, form: &Token, env: Ref) -> Result<Tail> {
+        let expansion = expand(keyword, form)?;
+        self.eval_tail(&expansion, env)
+    }
+}
+
+/// Rewrites the derived form `(keyword . form)` into forms closer to the
+/// core ones.
+fn expand(keyword: Symbol, form: &Token) -> Result<Token> {
+    match keyword {
+        symbol::LET_STAR => let_star(form),
+        symbol::COND => cond(form),
+        symbol::CASE => case(form),
+        symbol::AND => and(form),
+        symbol::OR => or(form),
+        symbol::WHEN => when(form, false),
+        symbol::UNLESS => when(form, true),
+        _ => Err(Error::BadSyntax(format!(
+            "{} is not a derived form",
+            keyword
+        ))),
+    }
+}
+
+/// `(let* (b1 b2 ...) body...)` becomes `(let (b1) (let* (b2 ...) body...))`.
+fn let_star(form: &Token) -> Result<Token> {
+    let bindings = items(nth(form, 0)?)?;
+    let body = rest(form)?.clone();
+    match bindings.split_first() {
+        Some((first, others)) if !others.is_empty() => Ok(Token::from(vec![
+            sym(symbol::LET),
+            Token::from(vec![first.clone()]),
+            cons(
+                sym(symbol::LET_STAR),
+                cons(Token::from(others.to_vec()), body),
+            ),
+        ])),
+        _ => Ok(cons(sym(symbol::LET), form.clone())),
+    }
+}
+
+/// Expands the first clause of a `cond`, leaving the others in a new `cond`.
+fn cond(clauses: &Token) -> Result<Token> {
+    let (clause, others) = match clauses {
+        Token::Cons { head, tail } => (items(head)?, tail.as_ref()),
+        _ => return Ok(Token::from(vec![sym(symbol::BEGIN)])),
+    };
+    let otherwise = cons(sym(symbol::COND), others.clone());
+    match clause.as_slice() {
+        [] => Err(Error::BadSyntax("empty cond clause".to_string())),
+        [keyword, body @ ..] if is_symbol(keyword, symbol::ELSE) => {
+            if *others != Token::EmptyList {
+                return Err(Error::BadSyntax(
+                    "else must be the last cond clause".to_string(),
+                ));
+            }
+            Ok(begin(body))
+        }
+        [test, arrow, receiver] if is_symbol(arrow, symbol::ARROW) => Ok(Token::from(vec![
+            sym(symbol::LET),
+            Token::from(vec![Token::from(vec![
+                sym(symbol::COND_VALUE),
+                test.clone(),
+            ])]),
+            Token::from(vec![
+                sym(symbol::IF),
+                sym(symbol::COND_VALUE),
+                funcall(receiver, symbol::COND_VALUE),
+                otherwise,
+            ]),
+        ])),
+        [test] => Ok(Token::from(vec![sym(symbol::OR), test.clone(), otherwise])),
+        [test, body @ ..] => Ok(Token::from(vec![
+            sym(symbol::IF),
+            test.clone(),
+            begin(body),
+            otherwise,
+        ])),
+    }
+}
+
+/// `(case key ((datum ...) body...) ... (else body...))` becomes a `cond`
+/// whose tests compare the value of `key` to each datum with `eqv?`. A body
+/// may also be `=> receiver`, which is called with the key.
+fn case(form: &Token) -> Result<Token> {
+    let mut clauses = vec![sym(symbol::COND)];
+    for clause in items(rest(form)?)? {
+        let clause = items(&clause)?;
+        let (data, body) = match clause.split_first() {
+            Some((data, body)) => (data, body),
+            None => return Err(Error::BadSyntax("empty case clause".to_string())),
+        };
+        let test = match data {
+            data if is_symbol(data, symbol::ELSE) => data.clone(),
+            Token::Cons { .. } | Token::EmptyList => {
+                let mut test = vec![sym(symbol::OR)];
+                for datum in items(data)? {
+                    test.push(Token::from(vec![
+                        sym(symbol::EQV),
+                        sym(symbol::CASE_KEY),
+                        Token::from(vec![sym(symbol::QUOTE), datum]),
+                    ]));
+                }
+                Token::from(test)
+            }
+            _ => {
+                return Err(Error::BadSyntax(format!(
+                    "case clause starts with {} rather than a list of data or else",
+                    data
+                )))
+            }
+        };
+        let body = match body {
+            [arrow, receiver] if is_symbol(arrow, symbol::ARROW) => {
+                vec![funcall(receiver, symbol::CASE_KEY)]
+            }
+            body => body.to_vec(),
+        };
+        clauses.push(cons(test, Token::from(body)));
+    }
+    Ok(Token::from(vec![
+        sym(symbol::LET),
+        Token::from(vec![Token::from(vec![
+            sym(symbol::CASE_KEY),
+            nth(form, 0)?.clone(),
+        ])]),
+        Token::from(clauses),
+    ]))
+}
+
+/// `(and a b ...)` becomes `(if a (and b ...) #f)`.
+fn and(form: &Token) -> Result<Token> {
+    match items(form)?.as_slice() {
+        [] => Ok(Token::Boolean { value: true }),
+        [only] => Ok(only.clone()),
+        [first, ..] => Ok(Token::from(vec![
+            sym(symbol::IF),
+            first.clone(),
+            cons(sym(symbol::AND), rest(form)?.clone()),
+            Token::Boolean { value: false },
+        ])),
+    }
+}
+
+/// `(or a b ...)` becomes `(let ((v a)) (if v v (or b ...)))`.
+fn or(form: &Token) -> Result<Token> {
+    match items(form)?.as_slice() {
+        [] => Ok(Token::Boolean { value: false }),
+        [only] => Ok(only.clone()),
+        [first, ..] => Ok(Token::from(vec![
+            sym(symbol::LET),
+            Token::from(vec![Token::from(vec![
+                sym(symbol::OR_VALUE),
+                first.clone(),
+            ])]),
+            Token::from(vec![
+                sym(symbol::IF),
+                sym(symbol::OR_VALUE),
+                sym(symbol::OR_VALUE),
+                cons(sym(symbol::OR), rest(form)?.clone()),
+            ]),
+        ])),
+    }
+}
+
+/// `(when test body...)` becomes `(if test (begin body...))`, and `unless`
+/// swaps the branches.
+fn when(form: &Token, negate: bool) -> Result<Token> {
+    let test = nth(form, 0)?.clone();
+    let body = cons(sym(symbol::BEGIN), rest(form)?.clone());
+    let nothing = Token::from(vec![sym(symbol::BEGIN)]);
+    let (consequent, alternative) = if negate {
+        (nothing, body)
+    } else {
+        (body, nothing)
+    };
+    Ok(Token::from(vec![
+        sym(symbol::IF),
+        test,
+        consequent,
+        alternative,
+    ]))
+}
+
+/// The elements of a proper list.
+fn items(list: &Token) -> Result<Vec<Token>> {
+    let mut items = Vec::new();
+    let mut cursor = list;
+    loop {
+        match cursor {
+            Token::Cons { head, tail } => {
+                items.push(head.as_ref().clone());
+                cursor = tail;
+            }
+            Token::EmptyList | Token::Nil => return Ok(items),
+            _ => return Err(Error::BadSyntax(format!("{} is not a proper list", list))),
+        }
+    }
+}
+
+fn is_symbol(token: &Token, name: Symbol) -> bool {
+    matches!(token, Token::Symbol { value } if *value == name)
+}
+
+fn sym(value: Symbol) -> Token {
+    Token::Symbol { value }
+}
+
+fn cons(head: Token, tail: Token) -> Token {
+    Token::Cons {
+        head: Box::new(head),
+        tail: Box::new(tail),
+    }
+}
+
+fn begin(body: &[Token]) -> Token {
+    cons(sym(symbol::BEGIN), Token::from(body.to_vec()))
+}
+
+/// `(funcall receiver name)`, which calls `receiver` in both Lisp-1 and
+/// Lisp-2 modes.
+fn funcall(receiver: &Token, name: Symbol) -> Token {
+    Token::from(vec![sym(symbol::FUNCALL), receiver.clone(), sym(name)])
+}
@@ -0,0 +1,88 @@
+//! Dynamically scoped variables, as in chapter 2: `dynamic-let`, `dynamic`
+//! and `dynamic-set!`, and the Common Lisp style `defvar`.
+//!
+//! The dynamic environment is the interpreter's `dynamic` stack rather than
+//! an argument of [`Interpreter::eval`]. Every form that binds dynamic
+//! variables truncates the stack back when it returns, whether normally or
+//! with an error, so each evaluation sees exactly the bindings it would if
+//! the environment were threaded through alongside the lexical one. Closures
+//! capture only the lexical environment.
+//!
+//! `(defvar name [value])` declares `name` special: from then on every
+//! reference to it and every `set!` of it is dynamic, and `lambda` binds it
+//! dynamically, as Common Lisp does. A special variable's global value is its
+//! ordinary global binding, which is also what `dynamic` falls back to.
+
+use crate::error::Result;
+use crate::heap::Ref;
+use crate::symbol::Symbol;
+use crate::token::Token;
+use crate::value::Value;
+
+use super::{nth, rest, symbol, Interpreter};
+
+impl Interpreter {
+    /// Handles `(dynamic-let ((name expr) ...) body...)`. The expressions
+    /// are all evaluated before any of the names is bound.
+    pub(super) fn eval_dynamic_let(&mut self, form: &Token, env: Ref) -> Result<Value> {
+        let bindings = self.eval_bindings(nth(form, 0)?, env)?;
+        let dynamic_base = self.dynamic.len();
+        self.dynamic.extend(bindings);
+        let result = self.eprogn(rest(form)?, env);
+        self.dynamic.truncate(dynamic_base);
+        result
+    }
+
+    /// Finds the innermost dynamic binding of `name`, or else its global
+    /// value.
+    pub fn lookup_dynamic(&self, name: Symbol) -> Result<Value> {
+        match self.dynamic.iter().rev().find(|(n, _)| *n == name) {
+            Some((_, value)) => Ok(value.clone()),
+            None => self.lookup(self.global, name),
+        }
+    }
+
+    /// Changes the innermost dynamic binding of `name`, or else its global
+    /// value.
+    pub fn update_dynamic(&mut self, name: Symbol, value: Value) -> Result<()> {
+        match self.dynamic.iter_mut().rev().find(|(n, _)| *n == name) {
+            Some((_, v)) => {
+                *v = value;
+                Ok(())
+            }
+            None => self.update(self.global, name, value),
+        }
+    }
+
+    pub fn is_special(&self, name: Symbol) -> bool {
+        self.specials.contains(&name)
+    }
+
+    /// Handles `(defvar name [value])`. As in Common Lisp, the value is only
+    /// evaluated and assigned if `name` has no global value yet.
+    pub(super) fn eval_defvar(&mut self, form: &Token) -> Result<Value> {
+        let name = symbol(nth(form, 0)?)?;
+        self.specials.insert(name);
+        if let Ok(expr) = nth(form, 1) {
+            if self.lookup(self.global, name).is_err() {
+                let value = self.eval(expr, self.global)?;
+                self.define(self.global, name, value);
+            }
+        }
+        Ok(Value::Symbol(name))
+    }
+
+    /// Moves the bindings of special variables out of the new frame `env`
+    /// onto the dynamic stack. The caller truncates the stack afterwards.
+    pub(super) fn bind_specials(&mut self, env: Ref) {
+        if self.specials.is_empty() {
+            return;
+        }
+        let bindings = std::mem::take(&mut self.frame_mut(env).bindings);
+        let (special, lexical): (Vec<_>, Vec<_>) = bindings
+            .into_iter()
+            .partition(|(name, _)| self.specials.contains(name));
+        self.frame_mut(env).bindings = lexical;
+        self.dynamic.extend(special);
+    }
+}
@@ -0,0 +1,55 @@
+//! The `catch` and `throw` escapes of chapter 3.
+//!
+//! `(catch tag body...)` evaluates `tag` and then `body`, and
+//! `(throw tag value)` makes the innermost active `catch` whose tag is `eq?`
+//! to its own return `value` at once. The throw is checked against the stack
+//! of active catches before anything unwinds, so a throw with no catcher
+//! fails where it happens. Otherwise it unwinds as an [`Error::Throw`], which
+//! restores the dynamic bindings on its way just as other errors do, and
+//! `catch` drops any roots pushed by the forms it abandoned.
+
+use crate::error::{Error, Result};
+use crate::heap::Ref;
+use crate::token::Token;
+use crate::value::Value;
+
+use super::{nth, rest, Interpreter};
+
+impl Interpreter {
+    /// Handles `(catch tag body...)`.
+    pub(super) fn eval_catch(&mut self, form: &Token, env: Ref) -> Result<Value> {
+        let tag = self.eval(nth(form, 0)?, env)?;
+        let depth = self.catchers.len();
+        let base = self.roots.len();
+        self.catchers.push(tag);
+        let result = self.eprogn(rest(form)?, env);
+        self.catchers.truncate(depth);
+        self.roots.truncate(base);
+        match result {
+            Err(Error::Throw(target)) if target == depth => {
+                Ok(self.thrown.take().unwrap_or_else(Value::unspecified))
+            }
+            result => result,
+        }
+    }
+
+    /// Handles `(throw tag value)`.
+    pub(super) fn eval_throw(&mut self, form: &Token, env: Ref) -> Result<Value> {
+        let base = self.roots.len();
+        let tag = self.eval(nth(form, 0)?, env)?;
+        self.root(&tag);
+        let value = self.eval(nth(form, 1)?, env)?;
+        self.roots.truncate(base);
+        match self
+            .catchers
+            .iter()
+            .rposition(|catcher| catcher.is_eq(&tag))
+        {
+            Some(depth) => {
+                self.thrown = Some(value);
+                Err(Error::Throw(depth))
+            }
+            None => Err(Error::NoCatcher(self.print(&tag).to_string())),
+        }
+    }
+}
@@ -0,0 +1,51 @@
+//! The function namespace of chapter 2: `function`, `flet` and `labels`.
+//!
+//! In Lisp-1 mode these forms still work, but since there is only one
+//! namespace, `flet` and `labels` bind ordinary variables and `function` is
+//! the identity on names and `lambda` forms.
+
+use crate::error::{Error, Result};
+use crate::heap::Ref;
+use crate::symbol::LAMBDA;
+use crate::token::Token;
+use crate::value::Value;
+
+use super::{nth, rest, symbol, Interpreter, Tail};
+
+impl Interpreter {
+    /// Evaluates a function name or a `lambda` form, as found in `(function
+    /// f)` or at the head of an application in Lisp-2 mode.
+    pub(super) fn eval_function(&mut self, expr: &Token, env: Ref) -> Result<Value> {
+        match expr {
+            Token::Symbol { value } => self.lookup_function(env, *value),
+            Token::Cons { head, tail } if matches!(**head, Token::Symbol { value: LAMBDA }) => {
+                Ok(self.make_function(nth(tail, 0)?, rest(tail)?, env))
+            }
+            _ => Err(Error::BadSyntax(format!(
+                "{} is neither a function name nor a lambda form",
+                expr
+            ))),
+        }
+    }
+
+    /// Handles `(flet ((name params body...) ...) body...)`, and `labels`
+    /// when `recursive` is set. The functions of `flet` are closed over the
+    /// enclosing environment, so they cannot call each other; those of
+    /// `labels` are closed over the new frame that binds them.
+    pub(super) fn eval_flet(&mut self, form: &Token, env: Ref, recursive: bool) -> Result<Tail> {
+        let frame = self.new_frame(Vec::new(), env);
+        let base = self.roots.len();
+        self.roots.push(frame);
+        let closure_env = if recursive { frame } else { env };
+        let mut definitions = nth(form, 0)?;
+        while let Token::Cons { head, tail } = definitions {
+            let name = symbol(nth(head, 0)?)?;
+            let function = self.make_function(nth(head, 1)?, rest(rest(head)?)?, closure_env);
+            self.define_function(frame, name, function);
+            definitions = tail;
+        }
+        let result = self.eval_scope(rest(form)?, frame);
+        self.roots.truncate(base);
+        result
+    }
+}
@@ -0,0 +1,241 @@
+//! Local and recursive bindings: `let`, named `let`, `letrec` and `letrec*`,
+//! the `do` loop, and the scanning of internal definitions.
+//!
+//! A body's internal definitions behave as a `letrec*`. On entry to the body
+//! each defined name is bound as [`Value::Unassigned`] in the new frame, so
+//! every closure in the body already sees the local binding, and referring
+//! to a name before its definition has run is an error rather than a lookup
+//! of some outer variable.
+
+use std::ops::ControlFlow;
+
+use crate::error::{Error, Result};
+use crate::heap::Ref;
+use crate::symbol::{Symbol, DEFINE};
+use crate::token::Token;
+use crate::value::Value;
+
+use super::{nth, rest, symbol, Interpreter, Mode, Tail};
+
+impl Interpreter {
+    /// Evaluates `body` in `env`, a frame just created for it, the last form
+    /// in tail position. The special variables among the frame's bindings
+    /// are bound dynamically and the body's internal definitions are bound
+    /// as unassigned.
+    pub(super) fn eval_scope(&mut self, body: &Token, env: Ref) -> Result<Tail> {
+        let base = self.roots.len();
+        self.roots.push(env);
+        let dynamic_base = self.dynamic.len();
+        self.bind_specials(env);
+        self.bind_definitions(body, env);
+        self.collect_if_due();
+        let result = if self.dynamic.len() == dynamic_base {
+            self.eprogn_tail(body, env)
+        } else {
+            // The last call has to be made while the dynamic bindings hold.
+            self.eprogn(body, env).map(Tail::Value)
+        };
+        self.dynamic.truncate(dynamic_base);
+        self.roots.truncate(base);
+        result
+    }
+
+    /// Binds the names defined at the top level of `body` as unassigned in
+    /// `env`, unless they are already bound there, as parameters for
+    /// instance. In Lisp-2 mode `(define (name ...) ...)` binds a function.
+    fn bind_definitions(&mut self, body: &Token, env: Ref) {
+        let lisp2 = self.mode == Mode::Lisp2;
+        let frame = self.frame_mut(env);
+        let mut cursor = body;
+        while let Token::Cons { head, tail } = cursor {
+            if let Some((name, function)) = definition(head) {
+                let bindings = if function && lisp2 {
+                    &mut frame.functions
+                } else {
+                    &mut frame.bindings
+                };
+                if !bindings.iter().any(|(n, _)| *n == name) {
+                    bindings.push((name, Value::Unassigned));
+                }
+            }
+            cursor = tail;
+        }
+    }
+
+    /// Evaluates the expressions of `((name expr) ...)` in `env`, returning
+    /// the names paired with their values.
+    pub(super) fn eval_bindings(
+        &mut self,
+        bindings: &Token,
+        env: Ref,
+    ) -> Result<Vec<(Symbol, Value)>> {
+        let base = self.roots.len();
+        let mut values = Vec::new();
+        let mut cursor = bindings;
+        while let Token::Cons { head, tail } = cursor {
+            let name = symbol(nth(head, 0)?)?;
+            let value = self.eval(nth(head, 1)?, env)?;
+            self.root(&value);
+            values.push((name, value));
+            cursor = tail;
+        }
+        self.roots.truncate(base);
+        Ok(values)
+    }
+
+    /// Handles `(let ((name expr) ...) body...)` and named `let`.
+    pub(super) fn eval_let(&mut self, form: &Token, env: Ref) -> Result<Tail> {
+        if let Token::Symbol { value: name } = nth(form, 0)? {
+            return self.eval_named_let(*name, rest(form)?, env);
+        }
+        let bindings = self.eval_bindings(nth(form, 0)?, env)?;
+        let frame = self.new_frame(bindings, env);
+        self.eval_scope(rest(form)?, frame)
+    }
+
+    /// Handles `(let name ((var expr) ...) body...)`: binds `name` to a
+    /// procedure of the `var`s, visible only in its own body, and calls it
+    /// with the values of the `expr`s. In Lisp-2 mode `name` is a function.
+    fn eval_named_let(&mut self, name: Symbol, form: &Token, env: Ref) -> Result<Tail> {
+        let bindings = self.eval_bindings(nth(form, 0)?, env)?;
+        let (names, values): (Vec<_>, Vec<_>) = bindings.into_iter().unzip();
+        let params = Token::from(
+            names
+                .into_iter()
+                .map(|value| Token::Symbol { value })
+                .collect::<Vec<_>>(),
+        );
+        let frame = self.new_frame(Vec::new(), env);
+        let function = self.make_function(&params, rest(form)?, frame);
+        self.define_function(frame, name, function.clone());
+        Ok(Tail::Call(function, values))
+    }
+
+    /// Handles `(do ((var init [step]) ...) (test result...) body...)`. Each
+    /// iteration binds the variables in a fresh frame, so closures made by
+    /// the body keep their own iteration's values, but the loop runs in
+    /// constant stack space. A variable without a step keeps its value.
+    pub(super) fn eval_do(&mut self, form: &Token, env: Ref) -> Result<Value> {
+        let mut steps = Vec::new();
+        let mut cursor = nth(form, 0)?;
+        while let Token::Cons { head, tail } = cursor {
+            let name = symbol(nth(head, 0)?)?;
+            steps.push((name, nth(head, 2).or_else(|_| nth(head, 0))?));
+            cursor = tail;
+        }
+        let exit = match nth(form, 1)? {
+            exit @ Token::Cons { .. } => exit,
+            _ => return Err(Error::BadSyntax("do is missing its test".to_string())),
+        };
+        let body = rest(rest(form)?)?;
+        let bindings = self.eval_bindings(nth(form, 0)?, env)?;
+        let mut frame = self.new_frame(bindings, env);
+        let base = self.roots.len();
+        self.roots.push(frame);
+        let dynamic_base = self.dynamic.len();
+        let result = loop {
+            self.bind_specials(frame);
+            let next = self.do_iteration(&steps, exit, body, frame);
+            self.dynamic.truncate(dynamic_base);
+            match next {
+                Ok(ControlFlow::Continue(bindings)) => {
+                    frame = self.new_frame(bindings, env);
+                    self.roots[base] = frame;
+                    self.collect_if_due();
+                }
+                Ok(ControlFlow::Break(value)) => break Ok(value),
+                Err(err) => break Err(err),
+            }
+        };
+        self.roots.truncate(base);
+        result
+    }
+
+    /// Runs one iteration of a `do` loop in `frame`. If the test of `exit`
+    /// holds, evaluates its results and breaks with the last value;
+    /// otherwise evaluates the body and continues with the values of the
+    /// steps.
+    fn do_iteration(
+        &mut self,
+        steps: &[(Symbol, &Token)],
+        exit: &Token,
+        body: &Token,
+        frame: Ref,
+    ) -> Result<ControlFlow<Value, Vec<(Symbol, Value)>>> {
+        if !self.eval(nth(exit, 0)?, frame)?.is_false() {
+            return Ok(ControlFlow::Break(self.eprogn(rest(exit)?, frame)?));
+        }
+        self.eprogn(body, frame)?;
+        let base = self.roots.len();
+        let mut bindings = Vec::new();
+        for (name, step) in steps {
+            let value = self.eval(step, frame)?;
+            self.root(&value);
+            bindings.push((*name, value));
+        }
+        self.roots.truncate(base);
+        Ok(ControlFlow::Continue(bindings))
+    }
+
+    /// Handles `(letrec ((name expr) ...) body...)`, and `letrec*` when
+    /// `sequential` is set. Every name is in scope in every expression, but
+    /// only `letrec*` assigns each value before evaluating the next
+    /// expression; `letrec` assigns them all at the end.
+    pub(super) fn eval_letrec(&mut self, form: &Token, env: Ref, sequential: bool) -> Result<Tail> {
+        let mut names = Vec::new();
+        let mut cursor = nth(form, 0)?;
+        while let Token::Cons { head, tail } = cursor {
+            names.push((symbol(nth(head, 0)?)?, Value::Unassigned));
+            cursor = tail;
+        }
+        let frame = self.new_frame(names, env);
+        let base = self.roots.len();
+        self.roots.push(frame);
+        let mut values = Vec::new();
+        let mut cursor = nth(form, 0)?;
+        while let Token::Cons { head, tail } = cursor {
+            let name = symbol(nth(head, 0)?)?;
+            let value = self.eval(nth(head, 1)?, frame)?;
+            if sequential {
+                self.define(frame, name, value);
+            } else {
+                self.root(&value);
+                values.push((name, value));
+            }
+            cursor = tail;
+        }
+        for (name, value) in values {
+            self.define(frame, name, value);
+        }
+        let result = self.eval_scope(rest(form)?, frame);
+        self.roots.truncate(base);
+        result
+    }
+}
+
+/// The name bound by a `define` form, and whether it defines a function.
+fn definition(form: &Token) -> Option<(Symbol, bool)> {
+    let (head, tail) = match form {
+        Token::Cons { head, tail } => (head, tail),
+        _ => return None,
+    };
+    if !matches!(**head, Token::Symbol { value: DEFINE }) {
+        return None;
+    }
+    match nth(tail, 0).ok()? {
+        Token::Symbol { value } => Some((*value, false)),
+        Token::Cons { head, .. } => match **head {
+            Token::Symbol { value } => Some((value, true)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Fails if `value` is the placeholder of a variable not yet initialized.
+pub(super) fn check_assigned(name: Symbol, value: Value) -> Result<Value> {
+    match value {
+        Value::Unassigned => Err(Error::UninitializedVariable(name.name().to_string())),
+        value => Ok(value),
+    }
+}
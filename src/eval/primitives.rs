@@ -0,0 +1,372 @@
+use std::cmp::Ordering;
+
+use crate::error::{Error, Result};
+use crate::heap::Heap;
+use crate::number::Number;
+use crate::reader::read_number;
+use crate::symbol::{self, Symbol};
+use crate::value::{Primitive, Value};
+
+use super::Interpreter;
+
+pub const PRIMITIVES: &[Primitive] = &[
+    Primitive::new("+", add),
+    Primitive::new("-", subtract),
+    Primitive::new("*", multiply),
+    Primitive::new("/", divide),
+    Primitive::new("quotient", quotient),
+    Primitive::new("remainder", remainder),
+    Primitive::new("modulo", modulo),
+    Primitive::new("=", num_eq),
+    Primitive::new("<", less),
+    Primitive::new(">", greater),
+    Primitive::new("<=", less_eq),
+    Primitive::new(">=", greater_eq),
+    Primitive::new("abs", abs),
+    Primitive::new("min", min),
+    Primitive::new("max", max),
+    Primitive::new("gcd", gcd),
+    Primitive::new("lcm", lcm),
+    Primitive::new("floor", floor),
+    Primitive::new("ceiling", ceiling),
+    Primitive::new("round", round),
+    Primitive::new("truncate", truncate),
+    Primitive::new("exact->inexact", exact_to_inexact),
+    Primitive::new("inexact->exact", inexact_to_exact),
+    Primitive::new("number->string", number_to_string),
+    Primitive::new("string->number", string_to_number),
+    Primitive::new("cons", cons),
+    Primitive::new("car", car),
+    Primitive::new("cdr", cdr),
+    Primitive::new("set-car!", set_car),
+    Primitive::new("set-cdr!", set_cdr),
+    Primitive::new("list", list),
+    Primitive::new("eq?", eq),
+    Primitive::new("eqv?", eqv),
+    Primitive::new("null?", null),
+    Primitive::new("pair?", pair),
+    Primitive::new("funcall", funcall),
+    Primitive::new("gc", gc),
+];
+
+/// Primitives that the derived forms call, under names of their own.
+pub const DERIVED: &[(Symbol, Primitive)] = &[
+    (symbol::EQV, Primitive::new("eqv?", eqv)),
+    (symbol::FUNCALL, Primitive::new("funcall", funcall)),
+];
+
+fn number(heap: &Heap, value: &Value) -> Result<Number> {
+    Number::from_value(value)
+        .ok_or_else(|| Error::WrongType(format!("{} is not a number", value.write(heap))))
+}
+
+fn arity(name: &str, args: &[Value], expected: usize) -> Result<()> {
+    if args.len() == expected {
+        Ok(())
+    } else {
+        Err(Error::WrongArity(format!(
+            "{} expects {} arguments, got {}",
+            name,
+            expected,
+            args.len()
+        )))
+    }
+}
+
+/// Checks that a procedure taking optional arguments got between `min` and
+/// `max` arguments.
+fn arity_between(name: &str, args: &[Value], min: usize, max: usize) -> Result<()> {
+    if (min..=max).contains(&args.len()) {
+        Ok(())
+    } else {
+        Err(Error::WrongArity(format!(
+            "{} expects {} to {} arguments, got {}",
+            name,
+            min,
+            max,
+            args.len()
+        )))
+    }
+}
+
+fn fold(
+    heap: &Heap,
+    args: &[Value],
+    init: Number,
+    op: fn(&Number, &Number) -> Result<Number>,
+) -> Result<Value> {
+    let mut acc = init;
+    for arg in args.iter() {
+        acc = op(&acc, &number(heap, arg)?)?;
+    }
+    Ok(acc.into())
+}
+
+/// Applies `op` to the arguments from left to right, or to `identity` and
+/// the only argument, as `-` and `/` do.
+fn fold_inverse(
+    heap: &Heap,
+    name: &str,
+    args: &[Value],
+    identity: Number,
+    op: fn(&Number, &Number) -> Result<Number>,
+) -> Result<Value> {
+    match args.split_first() {
+        None => Err(Error::WrongArity(format!(
+            "{} expects at least 1 argument",
+            name
+        ))),
+        Some((first, [])) => op(&identity, &number(heap, first)?).map(Value::from),
+        Some((first, rest)) => fold(heap, rest, number(heap, first)?, op),
+    }
+}
+
+fn unary(heap: &Heap, name: &str, args: &[Value], op: fn(&Number) -> Number) -> Result<Value> {
+    arity(name, args, 1)?;
+    Ok(op(&number(heap, &args[0])?).into())
+}
+
+fn binary(
+    heap: &Heap,
+    name: &str,
+    args: &[Value],
+    op: fn(&Number, &Number) -> Result<Number>,
+) -> Result<Value> {
+    arity(name, args, 2)?;
+    op(&number(heap, &args[0])?, &number(heap, &args[1])?).map(Value::from)
+}
+
+fn add(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    fold(&interp.heap, &args, Number::Integer(0), |a, b| Ok(a.add(b)))
+}
+
+fn multiply(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    fold(&interp.heap, &args, Number::Integer(1), |a, b| Ok(a.mul(b)))
+}
+
+fn subtract(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    fold_inverse(&interp.heap, "-", &args, Number::Integer(0), |a, b| {
+        Ok(a.sub(b))
+    })
+}
+
+fn divide(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    fold_inverse(&interp.heap, "/", &args, Number::Integer(1), Number::div)
+}
+
+fn quotient(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    binary(&interp.heap, "quotient", &args, Number::quotient)
+}
+
+fn remainder(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    binary(&interp.heap, "remainder", &args, Number::remainder)
+}
+
+fn modulo(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    binary(&interp.heap, "modulo", &args, Number::modulo)
+}
+
+fn gcd(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    fold(&interp.heap, &args, Number::Integer(0), Number::gcd)
+}
+
+fn lcm(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    fold(&interp.heap, &args, Number::Integer(1), Number::lcm)
+}
+
+fn abs(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    unary(&interp.heap, "abs", &args, Number::abs)
+}
+
+fn floor(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    unary(&interp.heap, "floor", &args, Number::floor)
+}
+
+fn ceiling(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    unary(&interp.heap, "ceiling", &args, Number::ceiling)
+}
+
+fn round(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    unary(&interp.heap, "round", &args, Number::round)
+}
+
+fn truncate(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    unary(&interp.heap, "truncate", &args, Number::truncate)
+}
+
+fn exact_to_inexact(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    unary(&interp.heap, "exact->inexact", &args, Number::to_inexact)
+}
+
+fn inexact_to_exact(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    arity("inexact->exact", &args, 1)?;
+    number(&interp.heap, &args[0])?.to_exact().map(Value::from)
+}
+
+/// Returns the argument that `pick` orders first. The result is inexact if
+/// any argument is.
+fn extremum(heap: &Heap, name: &str, args: &[Value], pick: Ordering) -> Result<Value> {
+    let (first, rest) = args
+        .split_first()
+        .ok_or_else(|| Error::WrongArity(format!("{} expects at least 1 argument", name)))?;
+    let mut best = number(heap, first)?;
+    let mut exact = best.is_exact();
+    for arg in rest {
+        let arg = number(heap, arg)?;
+        exact &= arg.is_exact();
+        if arg.compare(&best) == Some(pick) {
+            best = arg;
+        }
+    }
+    Ok(if exact { best } else { best.to_inexact() }.into())
+}
+
+fn min(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    extremum(&interp.heap, "min", &args, Ordering::Less)
+}
+
+fn max(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    extremum(&interp.heap, "max", &args, Ordering::Greater)
+}
+
+/// Reads the optional radix argument of `number->string` and
+/// `string->number`.
+fn radix(heap: &Heap, args: &[Value]) -> Result<u32> {
+    match args.get(1) {
+        None => Ok(10),
+        Some(Value::Integer(radix @ (2 | 8 | 10 | 16))) => Ok(*radix as u32),
+        Some(other) => Err(Error::WrongType(format!(
+            "{} is not a radix",
+            other.write(heap)
+        ))),
+    }
+}
+
+fn number_to_string(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    arity_between("number->string", &args, 1, 2)?;
+    let radix = radix(&interp.heap, &args)?;
+    let number = number(&interp.heap, &args[0])?;
+    match number.to_string_radix(radix) {
+        Some(text) => Ok(text.as_str().into()),
+        None => Err(Error::WrongType(format!(
+            "cannot write {} in base {}",
+            args[0].write(&interp.heap),
+            radix
+        ))),
+    }
+}
+
+fn string_to_number(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    arity_between("string->number", &args, 1, 2)?;
+    let radix = radix(&interp.heap, &args)?;
+    match &args[0] {
+        Value::String(text) => {
+            Ok(read_number(text, radix).map_or(Value::Boolean(false), Value::from))
+        }
+        other => Err(Error::WrongType(format!(
+            "{} is not a string",
+            other.write(&interp.heap)
+        ))),
+    }
+}
+
+fn compare(heap: &Heap, args: Vec<Value>, test: fn(Ordering) -> bool) -> Result<Value> {
+    let numbers = args
+        .iter()
+        .map(|arg| number(heap, arg))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Value::Boolean(
+        numbers
+            .windows(2)
+            .all(|pair| pair[0].compare(&pair[1]).is_some_and(test)),
+    ))
+}
+
+fn num_eq(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    compare(&interp.heap, args, Ordering::is_eq)
+}
+
+fn less(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    compare(&interp.heap, args, Ordering::is_lt)
+}
+
+fn greater(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    compare(&interp.heap, args, Ordering::is_gt)
+}
+
+fn less_eq(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    compare(&interp.heap, args, Ordering::is_le)
+}
+
+fn greater_eq(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    compare(&interp.heap, args, Ordering::is_ge)
+}
+
+fn cons(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    arity("cons", &args, 2)?;
+    let mut args = args.into_iter();
+    Ok(interp.heap.cons(args.next().unwrap(), args.next().unwrap()))
+}
+
+fn car(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    arity("car", &args, 1)?;
+    interp.heap.car(&args[0])
+}
+
+fn cdr(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    arity("cdr", &args, 1)?;
+    interp.heap.cdr(&args[0])
+}
+
+fn set_car(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    arity("set-car!", &args, 2)?;
+    interp.heap.set_car(&args[0], args[1].clone())?;
+    Ok(Value::unspecified())
+}
+
+fn set_cdr(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    arity("set-cdr!", &args, 2)?;
+    interp.heap.set_cdr(&args[0], args[1].clone())?;
+    Ok(Value::unspecified())
+}
+
+fn list(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    Ok(interp.heap.list(args))
+}
+
+fn eq(_: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    arity("eq?", &args, 2)?;
+    Ok(Value::Boolean(args[0].is_eq(&args[1])))
+}
+
+fn eqv(_: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    arity("eqv?", &args, 2)?;
+    Ok(Value::Boolean(args[0].is_eqv(&args[1])))
+}
+
+fn null(_: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    arity("null?", &args, 1)?;
+    Ok(Value::Boolean(matches!(args[0], Value::EmptyList)))
+}
+
+fn pair(_: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    arity("pair?", &args, 1)?;
+    Ok(Value::Boolean(matches!(args[0], Value::Pair(_))))
+}
+
+/// `(funcall f args...)` applies the function value `f`, which in Lisp-2
+/// mode is how a function held in a variable is called.
+fn funcall(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    let mut args = args.into_iter();
+    let function = args.next().ok_or_else(|| {
+        Error::WrongArity("funcall expects at least 1 argument, got 0".to_string())
+    })?;
+    interp.invoke(&function, args.collect())
+}
+
+/// Runs a collection, returning the number of objects still live.
+fn gc(interp: &mut Interpreter, args: Vec<Value>) -> Result<Value> {
+    arity("gc", &args, 0)?;
+    interp.collect();
+    Ok(Value::Integer(interp.heap.stats().live as i64))
+}
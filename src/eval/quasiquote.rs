@@ -0,0 +1,114 @@
+//! `quasiquote`, which builds a structure from a template, evaluating only
+//! the parts marked with `unquote` and `unquote-splicing`.
+//!
+//! Templates nest. Each `quasiquote` inside a template adds a level, and each
+//! `unquote` or `unquote-splicing` removes one; only those that bring the
+//! level back to zero are evaluated; the others are kept as data.
+
+use crate::error::{Error, Result};
+use crate::heap::Ref;
+use crate::symbol::{self, Symbol};
+use crate::token::Token;
+use crate::value::Value;
+
+use super::Interpreter;
+
+impl Interpreter {
+    /// Evaluates the template of a `quasiquote` form.
+    pub(super) fn eval_quasiquote(&mut self, template: &Token, env: Ref) -> Result<Value> {
+        let base = self.roots.len();
+        let result = self.quasi(template, 1, env);
+        self.roots.truncate(base);
+        result
+    }
+
+    /// Builds the value of `template`, nested `depth` levels of `quasiquote`
+    /// deep. Every part built so far is rooted, since evaluating an
+    /// `unquote` may collect garbage.
+    fn quasi(&mut self, template: &Token, depth: usize, env: Ref) -> Result<Value> {
+        let (head, tail) = match template {
+            Token::Cons { head, tail } => (head, tail),
+            Token::Vector { items } => return self.quasi_vector(items, depth, env),
+            atom => return self.heap.alloc_datum(atom),
+        };
+        match keyword_form(template) {
+            Some((symbol::UNQUOTE, expr)) if depth == 1 => return self.eval(expr, env),
+            Some((symbol::UNQUOTE_SPLICING, _)) if depth == 1 => {
+                return Err(Error::BadSyntax(format!(
+                    "unquote-splicing outside a list in {}",
+                    template
+                )))
+            }
+            Some((keyword @ (symbol::UNQUOTE | symbol::UNQUOTE_SPLICING), expr)) => {
+                return self.quasi_form(keyword, expr, depth - 1, env)
+            }
+            Some((symbol::QUASIQUOTE, expr)) => {
+                return self.quasi_form(symbol::QUASIQUOTE, expr, depth + 1, env)
+            }
+            _ => {}
+        }
+        if let Some((symbol::UNQUOTE_SPLICING, expr)) = keyword_form(head) {
+            if depth == 1 {
+                let spliced = self.eval(expr, env)?;
+                self.root(&spliced);
+                let rest = self.quasi(tail, depth, env)?;
+                let items = self.heap.list_to_vec(&spliced)?;
+                return Ok(self.heap.list_with_tail(items, rest));
+            }
+        }
+        let head = self.quasi(head, depth, env)?;
+        self.root(&head);
+        let tail = self.quasi(tail, depth, env)?;
+        Ok(self.heap.cons(head, tail))
+    }
+
+    /// Builds a vector from the items of a template, splicing in any
+    /// `unquote-splicing` at this level.
+    fn quasi_vector(&mut self, items: &[Token], depth: usize, env: Ref) -> Result<Value> {
+        let mut values = Vec::new();
+        for item in items {
+            match keyword_form(item) {
+                Some((symbol::UNQUOTE_SPLICING, expr)) if depth == 1 => {
+                    let spliced = self.eval(expr, env)?;
+                    self.root(&spliced);
+                    values.extend(self.heap.list_to_vec(&spliced)?);
+                }
+                _ => {
+                    let value = self.quasi(item, depth, env)?;
+                    self.root(&value);
+                    values.push(value);
+                }
+            }
+        }
+        Ok(self.heap.vector(values))
+    }
+
+    /// Rebuilds `(keyword expr)`, processing `expr` at `depth`.
+    fn quasi_form(
+        &mut self,
+        keyword: Symbol,
+        expr: &Token,
+        depth: usize,
+        env: Ref,
+    ) -> Result<Value> {
+        let expr = self.quasi(expr, depth, env)?;
+        Ok(self.heap.list(vec![Value::Symbol(keyword), expr]))
+    }
+}
+
+/// Splits a form like `(unquote x)`, a symbol followed by exactly one datum.
+fn keyword_form(token: &Token) -> Option<(Symbol, &Token)> {
+    match token {
+        Token::Cons { head, tail } => match (head.as_ref(), tail.as_ref()) {
+            (
+                Token::Symbol { value },
+                Token::Cons {
+                    head: expr,
+                    tail: rest,
+                },
+            ) if **rest == Token::EmptyList => Some((*value, expr)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
@@ -0,0 +1,822 @@
+use pretty_assertions::assert_eq;
+
+use crate::error::{Error, Result};
+use crate::eval::{Interpreter, Mode};
+use crate::reader::read_lisp;
+
+/// Evaluates every form in `input`, returning the printed value of the last.
+fn eval_in(interp: &mut Interpreter, input: &str) -> Result<String> {
+    let mut input = input.as_bytes();
+    let mut result = String::new();
+    for token in read_lisp(&mut input)? {
+        let value = interp.eval_toplevel(&token)?;
+        result = interp.print(&value).to_string();
+    }
+    Ok(result)
+}
+
+fn eval_str(input: &str) -> Result<String> {
+    eval_in(&mut Interpreter::new(), input)
+}
+
+fn eval_lisp2(input: &str) -> Result<String> {
+    eval_in(&mut Interpreter::with_mode(Mode::Lisp2), input)
+}
+
+/// `test_eval!(name, input, expected)` evaluates in Lisp-1 mode;
+/// `test_eval!(lisp2 name, ...)` in Lisp-2 mode.
+macro_rules! test_eval {
+    ($name:ident, $input:expr, $expected:expr) => {
+        test_eval!(@ $name, eval_str, $input, $expected);
+    };
+    (lisp2 $name:ident, $input:expr, $expected:expr) => {
+        test_eval!(@ $name, eval_lisp2, $input, $expected);
+    };
+    (@ $name:ident, $eval:ident, $input:expr, $expected:expr) => {
+        #[test]
+        fn $name() {
+            let actual = $eval($input);
+            assert!(actual.is_ok(), "{:?}", actual.err());
+            assert_eq!(actual.unwrap(), $expected);
+        }
+    };
+}
+
+macro_rules! test_eval_err {
+    ($name:ident, $input:expr, $pattern:pat) => {
+        test_eval_err!(@ $name, eval_str, $input, $pattern);
+    };
+    (lisp2 $name:ident, $input:expr, $pattern:pat) => {
+        test_eval_err!(@ $name, eval_lisp2, $input, $pattern);
+    };
+    (@ $name:ident, $eval:ident, $input:expr, $pattern:pat) => {
+        #[test]
+        fn $name() {
+            let actual = $eval($input);
+            assert!(matches!(actual, Err($pattern)), "{:?}", actual);
+        }
+    };
+}
+
+test_eval!(eval_integer, "42", "42");
+test_eval!(eval_string, "\"hello\"", "\"hello\"");
+test_eval!(eval_quote_symbol, "'foo", "foo");
+test_eval!(eval_quote_list, "'(1 2 3)", "(1 2 3)");
+test_eval!(eval_quote_dotted, "'(1 . 2)", "(1 . 2)");
+test_eval!(eval_quote_in_list, "(list 'a '(b 'c))", "(a (b 'c))");
+test_eval!(eval_quasiquote_atom, "`a", "a");
+test_eval!(
+    eval_quasiquote_unquote,
+    "(define x 2) `(a ,x ,(+ x 1))",
+    "(a 2 3)"
+);
+test_eval!(
+    eval_quasiquote_splice,
+    "`(1 ,@(list 2 3) 4 ,@'())",
+    "(1 2 3 4)"
+);
+test_eval!(
+    eval_quasiquote_dotted_tail,
+    "(define x 2) `(1 . ,x)",
+    "(1 . 2)"
+);
+test_eval!(
+    eval_quasiquote_splice_before_tail,
+    "`(,@(list 1 2) . 3)",
+    "(1 2 . 3)"
+);
+test_eval!(
+    eval_quasiquote_nested,
+    "(define x 2) `(a `(b ,x))",
+    "(a `(b ,x))"
+);
+test_eval!(
+    eval_quasiquote_nested_unquote,
+    "(define x 2) `(a `(b ,(c ,x ,@(list x))))",
+    "(a `(b ,(c 2 2)))"
+);
+test_eval!(
+    eval_quasiquote_keeps_parts_during_gc,
+    "(car `(,(list 1 2) ,(gc)))",
+    "(1 2)"
+);
+test_eval!(
+    eval_quasiquote_vector,
+    "(define x 2) `#(1 ,x ,@(list 3 4) (a ,x))",
+    "#(1 2 3 4 (a 2))"
+);
+test_eval!(eval_boolean, "(list #t #false)", "(#t #f)");
+test_eval!(
+    eval_character,
+    "(list #\\a #\\space #\\x41)",
+    "(#\\a #\\space #\\A)"
+);
+test_eval!(eval_vector, "#(1 \"b\" (c))", "#(1 \"b\" (c))");
+test_eval!(eval_if_true, "(if (quote t) 1 2)", "1");
+test_eval!(eval_if_false_literal, "(if #f 1 2)", "2");
+test_eval!(eval_if_false, "(if nil 1 2)", "2");
+test_eval!(eval_if_empty_list, "(if (quote ()) 1 2)", "1");
+test_eval!(eval_begin, "(begin 1 2 3)", "3");
+test_eval!(eval_arithmetic, "(+ 1 (* 2 3) (- 10 4))", "13");
+test_eval!(eval_float_contagion, "(+ 1 0.5)", "1.5");
+test_eval!(
+    eval_integer_promotion,
+    "(+ 9223372036854775807 1)",
+    "9223372036854775808"
+);
+test_eval!(
+    eval_integer_demotion,
+    "(eq? (- (+ 9223372036854775807 1) 1) 9223372036854775807)",
+    "#t"
+);
+test_eval!(
+    eval_factorial,
+    "(define (fact n) (if (= n 0) 1 (* n (fact (- n 1)))))
+     (fact 25)",
+    "15511210043330985984000000"
+);
+test_eval!(eval_rational_sum, "(+ 1/2 1/3)", "5/6");
+test_eval!(eval_rational_to_integer, "(+ 1/2 1/2)", "1");
+test_eval!(eval_rational_float_contagion, "(* 2/3 1.5)", "1.0");
+test_eval!(eval_rational_compare, "(< 1/3 0.34 1/2 1)", "#t");
+test_eval!(eval_divide_exact, "(/ 6 4)", "3/2");
+test_eval!(eval_divide_integral, "(/ 6 3)", "2");
+test_eval!(eval_divide_reciprocal, "(/ 2)", "1/2");
+test_eval!(eval_divide_inexact, "(/ 1 4.0)", "0.25");
+test_eval!(
+    eval_quotient,
+    "(list (quotient 17 5) (quotient -17 5))",
+    "(3 -3)"
+);
+test_eval!(
+    eval_remainder,
+    "(list (remainder 17 5) (remainder -17 5) (remainder 17 -5))",
+    "(2 -2 2)"
+);
+test_eval!(
+    eval_modulo,
+    "(list (modulo 17 5) (modulo -17 5) (modulo 17 -5))",
+    "(2 3 -3)"
+);
+test_eval!(eval_quotient_inexact, "(quotient 17.0 5)", "3.0");
+test_eval!(
+    eval_abs,
+    "(list (abs -7) (abs -1/2) (abs -2.5))",
+    "(7 1/2 2.5)"
+);
+test_eval!(eval_min_exact, "(min 3 1/2 2)", "1/2");
+test_eval!(eval_max_inexact, "(max 3 2.0)", "3.0");
+test_eval!(eval_gcd, "(list (gcd 12 -18) (gcd))", "(6 0)");
+test_eval!(eval_lcm, "(list (lcm 4 6) (lcm))", "(12 1)");
+test_eval!(
+    eval_rounding_rational,
+    "(list (floor -7/2) (ceiling -7/2) (round -7/2) (truncate -7/2) (round 5/2))",
+    "(-4 -3 -4 -3 2)"
+);
+test_eval!(
+    eval_rounding_float,
+    "(list (floor 2.5) (ceiling 2.5) (round 2.5) (truncate -2.5))",
+    "(2.0 3.0 2.0 -2.0)"
+);
+test_eval!(eval_exact_to_inexact, "(exact->inexact 1/4)", "0.25");
+test_eval!(eval_inexact_to_exact, "(inexact->exact 0.5)", "1/2");
+test_eval!(
+    eval_number_to_string,
+    "(list (number->string 255 16) (number->string -1/2) (number->string 1.5))",
+    "(\"ff\" \"-1/2\" \"1.5\")"
+);
+test_eval!(
+    eval_string_to_number,
+    "(list (string->number \"#e1.5\") (string->number \"ff\" 16) (string->number \"abc\"))",
+    "(3/2 255 #f)"
+);
+test_eval!(
+    eval_string_to_number_huge_exponent,
+    "(list (string->number \"#e1e999999999\") (string->number \"#e1e3\"))",
+    "(#f 1000)"
+);
+test_eval!(eval_lambda, "((lambda (x y) (+ x y)) 3 4)", "7");
+test_eval!(
+    eval_lambda_rest,
+    "((lambda (x . rest) rest) 1 2 3)",
+    "(2 3)"
+);
+test_eval!(eval_lambda_variadic, "((lambda args args) 1 2)", "(1 2)");
+test_eval!(
+    eval_closure,
+    "(define make-adder (lambda (n) (lambda (x) (+ x n))))
+     ((make-adder 10) 5)",
+    "15"
+);
+test_eval!(
+    eval_set,
+    "(define counter 0)
+     (define (inc) (set! counter (+ counter 1)))
+     (inc) (inc)
+     counter",
+    "2"
+);
+test_eval!(
+    eval_fib,
+    "(define (fib n)
+       (if (<= n 2)
+           1
+           (+ (fib (- n 1)) (fib (- n 2)))))
+     (fib 15)",
+    "610"
+);
+test_eval!(
+    eval_list_primitives,
+    "(cons (car (quote (1 2))) (cdr (quote (3 4))))",
+    "(1 4)"
+);
+
+test_eval!(
+    eval_set_car_alias,
+    "(define x (list 1 2))
+     (define y x)
+     (set-car! y 10)
+     x",
+    "(10 2)"
+);
+test_eval!(
+    eval_set_cdr_shared_tail,
+    "(define tail (list 2 3))
+     (define a (cons 1 tail))
+     (define b (cons 0 tail))
+     (set-cdr! tail (quote (4)))
+     (list a b)",
+    "((1 2 4) (0 2 4))"
+);
+test_eval!(
+    eval_circular_list,
+    "(define x (list 1 2))
+     (set-cdr! (cdr x) x)
+     (list (car (cdr (cdr x)))
+           (if (eq? x (cdr (cdr x))) (quote same) (quote different)))",
+    "(1 same)"
+);
+
+test_eval!(eval_negate, "(- 5)", "-5");
+test_eval!(eval_less, "(< 1 2)", "#t");
+test_eval!(eval_not_less, "(< 2 1)", "#f");
+test_eval!(
+    eval_procedures_in_lists,
+    "(car (list car))",
+    "#<primitive car>"
+);
+test_eval!(eval_eq_symbols, "(eq? (quote a) (quote a))", "#t");
+test_eval!(eval_eq_same_pair, "(define x (quote (1))) (eq? x x)", "#t");
+test_eval!(
+    eval_eq_different_pairs,
+    "(eq? (quote (1)) (quote (1)))",
+    "#f"
+);
+test_eval!(eval_if_no_alternative, "(if nil 1)", "#f");
+test_eval!(eval_empty_begin, "(begin)", "#f");
+
+test_eval!(eval_lisp1_function, "(eq? #'car car)", "#t");
+test_eval!(eval_lisp1_funcall, "(funcall car '(1 2))", "1");
+test_eval!(
+    eval_lisp1_flet_binds_variables,
+    "(flet ((twice (x) (* 2 x))) (list (twice 3) (funcall twice 4)))",
+    "(6 8)"
+);
+test_eval!(
+    lisp2 eval_lisp2_separate_namespaces,
+    "(define (f x) (+ x 1)) (define f 10) (f f)",
+    "11"
+);
+test_eval!(lisp2 eval_lisp2_variable_named_list, "(define list 3) (list list)", "(3)");
+test_eval!(lisp2 eval_lisp2_function, "(funcall (function car) '(1 2))", "1");
+test_eval!(lisp2 eval_lisp2_sharp_quote_lambda, "(funcall #'(lambda (x) (* x 2)) 4)", "8");
+test_eval!(lisp2 eval_lisp2_lambda_head, "((lambda (x) (list x)) 1)", "(1)");
+test_eval!(
+    lisp2 eval_lisp2_function_argument,
+    "(define (apply-twice f x) (funcall f (funcall f x)))
+     (apply-twice #'cdr '(1 2 3))",
+    "(3)"
+);
+test_eval!(
+    lisp2 eval_lisp2_flet_not_recursive,
+    "(define (f) 'outer)
+     (flet ((f () 'inner) (g () (f)))
+       (list (f) (g)))",
+    "(inner outer)"
+);
+test_eval!(
+    lisp2 eval_lisp2_labels_recursive,
+    "(labels ((even? (n) (if (= n 0) #t (odd? (- n 1))))
+              (odd? (n) (if (= n 0) #f (even? (- n 1)))))
+       (list (even? 10) (odd? 10)))",
+    "(#t #f)"
+);
+test_eval!(
+    lisp2 eval_lisp2_functions_survive_gc,
+    "(define (f) (list 1 2)) (labels ((g () (gc) (f))) (g))",
+    "(1 2)"
+);
+test_eval_err!(
+    lisp2 eval_lisp2_variable_not_function,
+    "(define g #'car) (g '(1))",
+    Error::UnboundFunction(_)
+);
+test_eval_err!(
+    lisp2 eval_lisp2_computed_head,
+    "((function car) '(1))",
+    Error::BadSyntax(_)
+);
+test_eval_err!(
+    lisp2 eval_lisp2_function_not_variable,
+    "car",
+    Error::UnboundVariable(_)
+);
+
+test_eval!(
+    eval_dynamic_let,
+    "(define (show) (dynamic x)) (dynamic-let ((x 1)) (show))",
+    "1"
+);
+test_eval!(
+    eval_dynamic_not_lexical,
+    "(define x 'global)
+     (define (get) x)
+     (dynamic-let ((x 'dynamic)) (list x (get) (dynamic x)))",
+    "(global global dynamic)"
+);
+test_eval!(
+    eval_dynamic_let_nested,
+    "(define (get) (dynamic x))
+     (dynamic-let ((x 1)) (list (dynamic-let ((x 2)) (get)) (get)))",
+    "(2 1)"
+);
+test_eval!(
+    eval_dynamic_set,
+    "(define (bump) (dynamic-set! x (+ (dynamic x) 1)))
+     (dynamic-let ((x 1)) (bump) (dynamic x))",
+    "2"
+);
+test_eval!(
+    eval_dynamic_survives_gc,
+    "(define (get) (dynamic x)) (dynamic-let ((x (list 1 2))) (gc) (get))",
+    "(1 2)"
+);
+test_eval!(
+    eval_defvar_binds_dynamically,
+    "(defvar *depth* 0)
+     (define (depth) *depth*)
+     (define (nested *depth*) (depth))
+     (list (nested 5) (depth))",
+    "(5 0)"
+);
+test_eval!(
+    eval_defvar_keeps_value,
+    "(defvar *x* 1) (defvar *x* 2) *x*",
+    "1"
+);
+test_eval!(
+    eval_defvar_set,
+    "(defvar *x* 1)
+     (define (bump) (set! *x* (+ *x* 1)))
+     (list (dynamic-let ((*x* 10)) (bump) *x*) *x*)",
+    "(11 1)"
+);
+test_eval_err!(
+    eval_dynamic_unbound,
+    "(dynamic y)",
+    Error::UnboundVariable(_)
+);
+
+#[test]
+fn eval_dynamic_let_unwinds_on_error() {
+    let mut interp = Interpreter::new();
+    assert!(eval_in(&mut interp, "(dynamic-let ((x 1)) (car 1))").is_err());
+    let actual = eval_in(&mut interp, "(dynamic x)");
+    assert!(
+        matches!(actual, Err(Error::UnboundVariable(_))),
+        "{:?}",
+        actual
+    );
+}
+
+test_eval!(eval_let, "(let ((x 1) (y 2)) (+ x y))", "3");
+test_eval!(
+    eval_let_inits_outside,
+    "(define x 1) (let ((x 2) (y x)) y)",
+    "1"
+);
+test_eval!(
+    eval_named_let,
+    "(let loop ((i 0) (acc '()))
+       (if (= i 3) acc (loop (+ i 1) (cons i acc))))",
+    "(2 1 0)"
+);
+test_eval!(
+    eval_named_let_name_not_in_inits,
+    "(define (loop) 'outer) (let loop ((x (loop))) x)",
+    "outer"
+);
+test_eval!(
+    eval_tail_calls_in_constant_stack,
+    "(let loop ((i 0)) (if (= i 20000) 'done (loop (+ i 1))))",
+    "done"
+);
+test_eval!(
+    eval_tail_calls_through_derived_forms,
+    "(define (count n)
+       (cond ((= n 0) 'done)
+             (else (let* ((m (- n 1))) (when #t (begin (count m)))))))
+     (count 20000)",
+    "done"
+);
+test_eval!(
+    eval_mutual_tail_calls,
+    "(define (even? n) (if (= n 0) #t (odd? (- n 1))))
+     (define (odd? n) (if (= n 0) #f (even? (- n 1))))
+     (even? 20001)",
+    "#f"
+);
+test_eval!(
+    lisp2 eval_lisp2_tail_calls_in_constant_stack,
+    "(labels ((loop (i) (if (= i 20000) 'done (loop (+ i 1))))) (loop 0))",
+    "done"
+);
+test_eval!(
+    eval_letrec,
+    "(letrec ((even? (lambda (n) (if (= n 0) #t (odd? (- n 1)))))
+              (odd? (lambda (n) (if (= n 0) #f (even? (- n 1))))))
+       (even? 10))",
+    "#t"
+);
+test_eval!(eval_letrec_star, "(letrec* ((a 1) (b (+ a 1))) b)", "2");
+test_eval!(
+    eval_letrec_keeps_values_during_gc,
+    "(letrec ((a (list 1 2)) (b (begin (gc) (list 3)))) (list a b))",
+    "((1 2) (3))"
+);
+test_eval!(
+    eval_internal_defines,
+    "(define (f)
+       (define (even? n) (if (= n 0) #t (odd? (- n 1))))
+       (define (odd? n) (if (= n 0) #f (even? (- n 1))))
+       (even? 11))
+     (f)",
+    "#f"
+);
+test_eval!(
+    eval_internal_defines_sequential,
+    "(let () (define a 1) (define b (+ a 1)) b)",
+    "2"
+);
+test_eval!(
+    eval_internal_define_keeps_parameter,
+    "(define (f x) (define x (+ x 1)) x) (f 1)",
+    "2"
+);
+test_eval!(
+    lisp2 eval_lisp2_named_let,
+    "(let loop ((i 3) (acc '()))
+       (if (= i 0) acc (loop (- i 1) (cons i acc))))",
+    "(1 2 3)"
+);
+test_eval!(
+    lisp2 eval_lisp2_internal_function,
+    "(define (f) (define (g) 1) (define g 2) (list (g) g)) (f)",
+    "(1 2)"
+);
+test_eval_err!(
+    eval_letrec_uninitialized,
+    "(letrec ((a 1) (b (+ a 1))) b)",
+    Error::UninitializedVariable(_)
+);
+test_eval_err!(
+    eval_letrec_use_before_init,
+    "(letrec* ((a b) (b 1)) a)",
+    Error::UninitializedVariable(_)
+);
+test_eval_err!(
+    eval_internal_define_shadows,
+    "(define x 'outer) (define (f) (define y x) (define x 'inner) y) (f)",
+    Error::UninitializedVariable(_)
+);
+
+test_eval!(
+    eval_let_star,
+    "(let* ((x 1) (y (+ x 1))) (list x y))",
+    "(1 2)"
+);
+test_eval!(eval_let_star_empty, "(let* () 5)", "5");
+test_eval!(eval_and, "(list (and) (and 1 2) (and 1 #f 3))", "(#t 2 #f)");
+test_eval!(eval_or, "(list (or) (or #f 2) (or #f #f))", "(#f 2 #f)");
+test_eval!(
+    eval_or_short_circuits,
+    "(define x 0) (or 1 (set! x 1)) x",
+    "0"
+);
+test_eval!(
+    eval_or_does_not_capture,
+    "(let ((x 'outer)) (or #f x))",
+    "outer"
+);
+test_eval!(
+    eval_cond,
+    "(define (sign n) (cond ((< n 0) 'negative) ((= n 0) 'zero) (else 'positive)))
+     (list (sign -2) (sign 0) (sign 3))",
+    "(negative zero positive)"
+);
+test_eval!(
+    eval_cond_arrow,
+    "(cond ((cdr '(1 2)) => car) (else 'none))",
+    "2"
+);
+test_eval!(eval_cond_test_only, "(cond (#f) (2))", "2");
+test_eval!(eval_cond_no_match, "(cond (#f 1))", "#f");
+test_eval!(
+    eval_case,
+    "(define (kind x) (case x ((1 2 3) 'small) ((a b) 'letter) (else 'other)))
+     (list (kind 2) (kind 'b) (kind \"s\"))",
+    "(small letter other)"
+);
+test_eval!(
+    eval_case_arrow,
+    "(case 5 ((1) 'one) (else => (lambda (x) (* x 2))))",
+    "10"
+);
+test_eval!(
+    eval_case_signed_zero,
+    "(list (case -0.0 ((0.0) 'positive) ((-0.0) 'negative)) (case 1.0 ((1) 'exact) (else 'inexact)))",
+    "(negative inexact)"
+);
+test_eval!(
+    eval_eqv_numbers,
+    "(list (eqv? 0.0 -0.0) (eqv? 2.5 2.5) (eqv? +nan.0 +nan.0) (eqv? 1 1.0) (eqv? 1/2 2/4)
+           (eqv? 100000000000000000000 100000000000000000000))",
+    "(#f #t #t #f #t #t)"
+);
+test_eval!(
+    eval_case_does_not_capture_eqv,
+    "(let ((eqv? (lambda (a b) #t))) (case 1 ((2) 'two) (else 'other)))",
+    "other"
+);
+test_eval!(
+    eval_arrow_does_not_capture_funcall,
+    "(let ((funcall 0)) (list (cond ((car '(1)) => list)) (case 2 ((2) => list))))",
+    "((1) (2))"
+);
+test_eval!(
+    lisp2 eval_lisp2_arrow_does_not_capture_funcall,
+    "(flet ((funcall (f x) 'captured)) (cond ((car '(1)) => #'list)))",
+    "(1)"
+);
+test_eval!(
+    eval_case_nested_or,
+    "(case (car '(c)) ((a) 1) ((b c) (or #f 'found)))",
+    "found"
+);
+test_eval!(
+    eval_when_unless,
+    "(list (when (= 1 1) 'a 'b) (unless (= 1 1) 'c) (unless #f 'd))",
+    "(b #f d)"
+);
+test_eval!(
+    eval_do,
+    "(do ((i 0 (+ i 1)) (acc '() (cons i acc))) ((= i 3) acc))",
+    "(2 1 0)"
+);
+test_eval!(
+    eval_do_body,
+    "(define v (list 0))
+     (do ((i 0 (+ i 1))) ((= i 4) (car v)) (set-car! v (+ (car v) i)))",
+    "6"
+);
+test_eval!(
+    eval_do_many_iterations,
+    "(do ((i 0 (+ i 1))) ((= i 100000) i))",
+    "100000"
+);
+test_eval!(
+    eval_do_fresh_bindings,
+    "(define fs (do ((i 0 (+ i 1)) (fs '() (cons (lambda () i) fs))) ((= i 3) fs)))
+     (list ((car fs)) ((car (cdr fs))) ((car (cdr (cdr fs)))))",
+    "(2 1 0)"
+);
+test_eval!(
+    lisp2 eval_lisp2_cond_arrow,
+    "(cond ((cdr '(1 2)) => #'car))",
+    "2"
+);
+test_eval!(
+    lisp2 eval_lisp2_do,
+    "(do ((i 0 (+ i 1)) (acc '() (cons i acc))) ((= i 3) acc))",
+    "(2 1 0)"
+);
+test_eval_err!(
+    eval_cond_else_not_last,
+    "(cond (else 1) (#t 2))",
+    Error::BadSyntax(_)
+);
+test_eval_err!(
+    eval_case_bad_clause,
+    "(case 1 (1 'one))",
+    Error::BadSyntax(_)
+);
+test_eval_err!(
+    eval_do_bad_variable,
+    "(do ((1 2)) (#t))",
+    Error::BadSyntax(_)
+);
+
+test_eval!(eval_catch_throw, "(catch 'done (throw 'done 42) 1)", "42");
+test_eval!(eval_catch_no_throw, "(catch 'done 1 2)", "2");
+test_eval!(
+    eval_throw_from_procedure,
+    "(define (first-over n l)
+       (catch 'found
+         (do ((l l (cdr l))) ((null? l) #f)
+           (when (> (car l) n) (throw 'found (car l))))))
+     (list (first-over 2 '(1 2 3 4)) (first-over 5 '(1 2)))",
+    "(3 #f)"
+);
+test_eval!(
+    eval_throw_skips_other_tags,
+    "(catch 'a (list 1 (catch 'b (throw 'a 2))))",
+    "2"
+);
+test_eval!(
+    eval_throw_innermost,
+    "(catch 'a (list 1 (catch 'a (throw 'a 2))))",
+    "(1 2)"
+);
+test_eval!(
+    eval_catch_evaluates_tag,
+    "(define tag (list 'x)) (catch tag (throw tag 'caught))",
+    "caught"
+);
+test_eval!(
+    eval_throw_restores_dynamic,
+    "(define (get) (dynamic x))
+     (dynamic-let ((x 1))
+       (catch 'k (dynamic-let ((x 2)) (throw 'k 0)))
+       (get))",
+    "1"
+);
+test_eval_err!(
+    eval_throw_no_catch,
+    "(throw 'nowhere 1)",
+    Error::NoCatcher(_)
+);
+test_eval_err!(
+    eval_throw_other_tag,
+    "(catch 'a (throw 'b 1))",
+    Error::NoCatcher(_)
+);
+
+#[test]
+fn eval_catch_ends_with_its_body() {
+    let mut interp = Interpreter::new();
+    eval_in(&mut interp, "(define k 'tag) (catch k 1)").unwrap();
+    let actual = eval_in(&mut interp, "(throw k 2)");
+    assert!(matches!(actual, Err(Error::NoCatcher(_))), "{:?}", actual);
+}
+
+#[test]
+fn eval_gc_frees_garbage() {
+    let mut interp = Interpreter::new();
+    eval_in(&mut interp, "(define keep (list 1 2 3)) (list 4 5 6) (gc)").unwrap();
+    let stats = interp.heap.stats();
+    assert_eq!(stats.collections, 1);
+    assert_eq!(stats.freed, 3);
+    assert_eq!(eval_in(&mut interp, "keep").unwrap(), "(1 2 3)");
+}
+
+#[test]
+fn eval_gc_keeps_arguments_being_evaluated() {
+    let mut interp = Interpreter::new();
+    let actual = eval_in(&mut interp, "(cons (list 1 2) (begin (gc) (list 3)))").unwrap();
+    assert_eq!(actual, "((1 2) 3)");
+}
+
+#[test]
+fn eval_gc_keeps_closure_environments() {
+    let mut interp = Interpreter::new();
+    let actual = eval_in(
+        &mut interp,
+        "(define make-counter
+           (lambda (n)
+             (lambda () (set! n (+ n 1)) (gc) n)))
+         (define c (make-counter (car (list 10))))
+         (c)
+         (c)",
+    )
+    .unwrap();
+    assert_eq!(actual, "12");
+}
+
+#[test]
+fn eval_gc_collects_cycles() {
+    let mut interp = Interpreter::new();
+    eval_in(
+        &mut interp,
+        "(define (make-loop)
+           (define self (lambda () self))
+           self)
+         (define x (list 1 2))
+         (set-cdr! (cdr x) x)
+         (set! x 0)
+         (make-loop)
+         (gc)",
+    )
+    .unwrap();
+    let live = interp.heap.stats().live;
+    eval_in(&mut interp, "(make-loop) (gc)").unwrap();
+    assert_eq!(interp.heap.stats().live, live);
+}
+
+#[test]
+fn eval_gc_after_caught_throws() {
+    let mut interp = Interpreter::new();
+    let program = |n| {
+        format!(
+            "(do ((i 0 (+ i 1))) ((= i {}) (gc))
+               (catch 'k ((lambda (x) x) (throw 'k i))))",
+            n
+        )
+    };
+    eval_in(&mut interp, &program(1)).unwrap();
+    let live = interp.heap.stats().live;
+    eval_in(&mut interp, &program(1000)).unwrap();
+    assert_eq!(interp.heap.stats().live, live);
+}
+
+#[test]
+fn eval_automatic_collection() {
+    let mut interp = Interpreter::new();
+    let actual = eval_in(
+        &mut interp,
+        "(define (inner n)
+           (if (= n 0) 0 (begin (list n n) (inner (- n 1)))))
+         (define (outer n)
+           (if (= n 0) (list 0) (begin (inner 100) (outer (- n 1)))))
+         (outer 50)",
+    )
+    .unwrap();
+    assert_eq!(actual, "(0)");
+    assert!(interp.heap.stats().collections > 0);
+    assert!(interp.heap.stats().freed > 0);
+}
+
+#[test]
+fn eval_collection_between_forms() {
+    let mut interp = Interpreter::new();
+    let input = "(list 1 2 3 4 5)\n".repeat(4_000);
+    assert_eq!(eval_in(&mut interp, &input).unwrap(), "(1 2 3 4 5)");
+    assert!(interp.heap.stats().collections > 0);
+}
+
+test_eval_err!(
+    eval_unbound,
+    "undefined-variable",
+    Error::UnboundVariable(_)
+);
+test_eval_err!(eval_divide_by_zero, "(/ 1 0)", Error::DivisionByZero(_));
+test_eval_err!(
+    eval_modulo_by_zero,
+    "(modulo 1 0)",
+    Error::DivisionByZero(_)
+);
+test_eval_err!(
+    eval_quotient_rational,
+    "(quotient 1/2 1)",
+    Error::WrongType(_)
+);
+test_eval_err!(eval_bad_radix, "(number->string 10 3)", Error::WrongType(_));
+test_eval_err!(eval_unquote_outside, ",x", Error::BadSyntax(_));
+test_eval_err!(
+    eval_splice_in_dotted_tail,
+    "`(1 . ,@(list 2))",
+    Error::BadSyntax(_)
+);
+test_eval_err!(eval_splice_improper, "`(,@1)", Error::WrongType(_));
+test_eval_err!(
+    eval_set_unbound,
+    "(set! nowhere 1)",
+    Error::UnboundVariable(_)
+);
+test_eval_err!(eval_not_a_function, "(1 2)", Error::WrongType(_));
+test_eval_err!(eval_too_few, "((lambda (x y) x) 1)", Error::WrongArity(_));
+test_eval_err!(eval_too_many, "((lambda (x) x) 1 2)", Error::WrongArity(_));
+test_eval_err!(eval_bad_quote, "(quote)", Error::BadSyntax(_));
+
+#[test]
+fn eval_errors_show_forms_as_source() {
+    for (input, message) in [
+        ("(let ((x)) x)", "bad syntax: missing form in (x)"),
+        ("(set! 'x 1)", "bad syntax: 'x is not a symbol"),
+        (
+            "(inexact->exact +inf.0)",
+            "wrong type: +inf.0 has no exact value",
+        ),
+    ] {
+        assert_eq!(eval_str(input).unwrap_err().to_string(), message);
+    }
+}
@@ -0,0 +1,321 @@
+//! A mark-and-sweep heap for runtime objects.
+//!
+//! Pairs, vectors, closures and environment frames can all refer to each
+//! other, so reference counting would leak cycles such as a closure stored in
+//! its own environment. Instead they live in a [`Heap`] and are referred to by
+//! [`Ref`] handles. [`Heap::collect`] marks everything reachable from a set of
+//! roots and frees the rest.
+//!
+//! The heap never collects on its own: the owner decides when it is safe, and
+//! must pass every value it still holds as a root.
+
+use std::rc::Rc;
+
+use crate::error::{Error, Result};
+use crate::symbol::Symbol;
+use crate::token::Token;
+use crate::value::Value;
+
+/// A handle to an object on the [`Heap`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Ref(usize);
+
+pub enum Object {
+    Pair(Value, Value),
+    Vector(Vec<Value>),
+    Closure(Closure),
+    Frame(Frame),
+}
+
+/// A `lambda` together with the environment it closes over.
+pub struct Closure {
+    pub params: Rc<Token>,
+    pub body: Rc<Token>,
+    pub env: Ref,
+}
+
+/// One level of an environment: the variables bound by a single `lambda`
+/// invocation, or the global variables.
+pub struct Frame {
+    pub bindings: Vec<(Symbol, Value)>,
+    /// The function namespace, used only in Lisp-2 mode: the functions bound
+    /// by `flet` or `labels`, or the global functions.
+    pub functions: Vec<(Symbol, Value)>,
+    pub parent: Option<Ref>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Stats {
+    /// Objects currently allocated.
+    pub live: usize,
+    /// Collections run so far.
+    pub collections: usize,
+    /// Objects freed by all collections so far.
+    pub freed: usize,
+    /// Objects allocated since the last collection.
+    pub allocated: usize,
+}
+
+#[derive(Default)]
+pub struct Heap {
+    objects: Vec<Option<Object>>,
+    marks: Vec<bool>,
+    free: Vec<usize>,
+    stats: Stats,
+}
+
+impl Heap {
+    pub fn alloc(&mut self, object: Object) -> Ref {
+        self.stats.live += 1;
+        self.stats.allocated += 1;
+        match self.free.pop() {
+            Some(index) => {
+                self.objects[index] = Some(object);
+                Ref(index)
+            }
+            None => {
+                self.objects.push(Some(object));
+                self.marks.push(false);
+                Ref(self.objects.len() - 1)
+            }
+        }
+    }
+
+    pub fn get(&self, r: Ref) -> &Object {
+        self.objects[r.0].as_ref().expect("dangling heap reference")
+    }
+
+    pub fn get_mut(&mut self, r: Ref) -> &mut Object {
+        self.objects[r.0].as_mut().expect("dangling heap reference")
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    pub fn cons(&mut self, car: Value, cdr: Value) -> Value {
+        Value::Pair(self.alloc(Object::Pair(car, cdr)))
+    }
+
+    pub fn list(&mut self, items: Vec<Value>) -> Value {
+        self.list_with_tail(items, Value::EmptyList)
+    }
+
+    pub fn list_with_tail(&mut self, items: Vec<Value>, tail: Value) -> Value {
+        items
+            .into_iter()
+            .rev()
+            .fold(tail, |cdr, car| self.cons(car, cdr))
+    }
+
+    pub fn vector(&mut self, items: Vec<Value>) -> Value {
+        Value::Vector(self.alloc(Object::Vector(items)))
+    }
+
+    /// Returns the car and cdr of a pair.
+    pub fn pair(&self, value: &Value) -> Result<(&Value, &Value)> {
+        match value {
+            Value::Pair(r) => match self.get(*r) {
+                Object::Pair(car, cdr) => Ok((car, cdr)),
+                _ => unreachable!("pair reference to a non-pair"),
+            },
+            _ => Err(Error::WrongType(format!(
+                "{} is not a pair",
+                value.write(self)
+            ))),
+        }
+    }
+
+    pub fn car(&self, value: &Value) -> Result<Value> {
+        self.pair(value).map(|(car, _)| car.clone())
+    }
+
+    pub fn cdr(&self, value: &Value) -> Result<Value> {
+        self.pair(value).map(|(_, cdr)| cdr.clone())
+    }
+
+    fn pair_mut(&mut self, value: &Value) -> Result<(&mut Value, &mut Value)> {
+        match value {
+            Value::Pair(r) => match self.get_mut(*r) {
+                Object::Pair(car, cdr) => Ok((car, cdr)),
+                _ => unreachable!("pair reference to a non-pair"),
+            },
+            _ => Err(Error::WrongType(format!(
+                "{} is not a pair",
+                value.write(self)
+            ))),
+        }
+    }
+
+    pub fn set_car(&mut self, pair: &Value, value: Value) -> Result<()> {
+        *self.pair_mut(pair)?.0 = value;
+        Ok(())
+    }
+
+    pub fn set_cdr(&mut self, pair: &Value, value: Value) -> Result<()> {
+        *self.pair_mut(pair)?.1 = value;
+        Ok(())
+    }
+
+    /// Collects the elements of a proper list.
+    pub fn list_to_vec(&self, list: &Value) -> Result<Vec<Value>> {
+        let mut items = Vec::new();
+        let mut cursor = list;
+        loop {
+            match cursor {
+                Value::Pair(_) => {
+                    let (car, cdr) = self.pair(cursor)?;
+                    items.push(car.clone());
+                    cursor = cdr;
+                }
+                Value::EmptyList => return Ok(items),
+                _ => {
+                    return Err(Error::WrongType(format!(
+                        "{} is not a proper list",
+                        list.write(self)
+                    )))
+                }
+            }
+        }
+    }
+
+    /// Converts a datum read by the reader into a runtime value, allocating
+    /// its pairs and vectors on the heap.
+    pub fn alloc_datum(&mut self, token: &Token) -> Result<Value> {
+        match token {
+            Token::Cons { .. } => {
+                let mut items = Vec::new();
+                let mut cursor = token;
+                while let Token::Cons { head, tail } = cursor {
+                    items.push(self.alloc_datum(head)?);
+                    cursor = tail;
+                }
+                let tail = self.alloc_datum(cursor)?;
+                Ok(self.list_with_tail(items, tail))
+            }
+            Token::Vector { items } => {
+                let items = items
+                    .iter()
+                    .map(|item| self.alloc_datum(item))
+                    .collect::<Result<_>>()?;
+                Ok(self.vector(items))
+            }
+            atom => Value::atom(atom),
+        }
+    }
+
+    /// Frees every object not reachable from `roots`, returning the number
+    /// of objects freed.
+    pub fn collect<I: IntoIterator<Item = Ref>>(&mut self, roots: I) -> usize {
+        let mut pending: Vec<Ref> = roots.into_iter().collect();
+        while let Some(r) = pending.pop() {
+            if self.marks[r.0] {
+                continue;
+            }
+            self.marks[r.0] = true;
+            match self.get(r) {
+                Object::Pair(car, cdr) => {
+                    pending.extend(car.heap_ref());
+                    pending.extend(cdr.heap_ref());
+                }
+                Object::Vector(items) => {
+                    pending.extend(items.iter().filter_map(Value::heap_ref));
+                }
+                Object::Closure(closure) => pending.push(closure.env),
+                Object::Frame(frame) => {
+                    let bindings = frame.bindings.iter().chain(&frame.functions);
+                    pending.extend(bindings.filter_map(|(_, v)| v.heap_ref()));
+                    pending.extend(frame.parent);
+                }
+            }
+        }
+
+        let mut freed = 0;
+        for (index, (object, mark)) in self
+            .objects
+            .iter_mut()
+            .zip(self.marks.iter_mut())
+            .enumerate()
+        {
+            if object.is_some() && !*mark {
+                *object = None;
+                self.free.push(index);
+                freed += 1;
+            }
+            *mark = false;
+        }
+
+        self.stats.live -= freed;
+        self.stats.freed += freed;
+        self.stats.collections += 1;
+        self.stats.allocated = 0;
+        freed
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_collect_unreachable() {
+        let mut heap = Heap::default();
+        let kept = heap.list(vec![1.into(), 2.into()]);
+        heap.list(vec![3.into(), 4.into(), 5.into()]);
+        assert_eq!(heap.collect(kept.heap_ref()), 3);
+        assert_eq!(heap.stats().live, 2);
+        assert_eq!(heap.list_to_vec(&kept).unwrap(), vec![1.into(), 2.into()]);
+    }
+
+    #[test]
+    fn test_collect_cycle() {
+        let mut heap = Heap::default();
+        let pair = heap.cons(1.into(), Value::EmptyList);
+        heap.set_cdr(&pair, pair.clone()).unwrap();
+        assert_eq!(heap.collect(pair.heap_ref()), 0);
+        assert_eq!(heap.collect(None), 1);
+        assert_eq!(heap.stats().live, 0);
+    }
+
+    #[test]
+    fn test_reuse_freed_slots() {
+        let mut heap = Heap::default();
+        heap.cons(1.into(), 2.into());
+        heap.collect(None);
+        heap.cons(3.into(), 4.into());
+        assert_eq!(heap.objects.len(), 1);
+    }
+
+    #[test]
+    fn test_set_cdr_shared() {
+        let mut heap = Heap::default();
+        let tail = heap.list(vec![2.into()]);
+        let list = heap.cons(1.into(), tail.clone());
+        let three = heap.list(vec![3.into()]);
+        heap.set_cdr(&tail, three).unwrap();
+        assert_eq!(
+            heap.list_to_vec(&list).unwrap(),
+            vec![1.into(), 2.into(), 3.into()]
+        );
+    }
+
+    #[test]
+    fn test_list_to_vec_improper() {
+        let mut heap = Heap::default();
+        let value = heap.cons(1.into(), 2.into());
+        assert!(heap.list_to_vec(&value).is_err());
+    }
+
+    #[test]
+    fn test_alloc_datum() {
+        let mut heap = Heap::default();
+        let token: Token = vec![Token::Integer { value: 1 }, Token::Nil].into();
+        let value = heap.alloc_datum(&token).unwrap();
+        assert_eq!(
+            heap.list_to_vec(&value).unwrap(),
+            vec![1.into(), Value::Boolean(false)]
+        );
+    }
+}
@@ -1,3 +1,10 @@
 pub mod error;
+pub mod eval;
+pub mod heap;
+pub mod number;
+pub mod printer;
 pub mod reader;
+pub mod repl;
+pub mod symbol;
 pub mod token;
+pub mod value;
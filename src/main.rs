@@ -1,34 +1,122 @@
-use std::io::BufReader;
-use std::{fs::File, path::PathBuf};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::thread;
 
 use clap::{Parser, Subcommand};
 use clap_verbosity_flag::Verbosity;
 use human_panic::setup_panic;
 
-use lisp::error::Result;
-use lisp::reader::read_lisp;
+use lisp::error::{Error, Result};
+use lisp::eval::{Interpreter, Mode, STACK_SIZE};
+use lisp::reader::{read_lisp_spanned, Reader};
+use lisp::repl::repl;
+use lisp::token::Token;
 
-fn main() -> Result<()> {
+// `setup_panic!` still expands to the deprecated `PanicInfo` alias.
+#[allow(deprecated)]
+fn main() {
     setup_panic!();
     let args = Cli::parse();
     env_logger::Builder::new()
         .filter_level(args.verbose.log_level_filter())
         .init();
 
-    match args.command {
-        Command::Parse { input } => {
-            let reader = File::open(input)?;
-            let mut buf_reader = BufReader::new(reader);
-            let tokens = read_lisp(&mut buf_reader)?;
-            for token in tokens {
+    // Evaluation recurses, so give it a larger stack than the main thread's.
+    let result = match thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(move || run(args))
+    {
+        Ok(handle) => handle
+            .join()
+            .unwrap_or_else(|payload| std::panic::resume_unwind(payload)),
+        Err(err) => Err(err.into()),
+    };
+    if let Err(err) = result {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+}
+
+fn run(args: Cli) -> Result<()> {
+    let mode = if args.lisp2 { Mode::Lisp2 } else { Mode::Lisp1 };
+    let mut interp = Interpreter::with_mode(mode);
+    let result = run_command(&mut interp, args.command);
+
+    // Report on the heap even if the command failed.
+    if args.gc_stats {
+        let stats = interp.heap.stats();
+        eprintln!(
+            "gc: {} live objects, {} collections, {} objects freed",
+            stats.live, stats.collections, stats.freed
+        );
+    }
+
+    result
+}
+
+fn run_command(interp: &mut Interpreter, command: Command) -> Result<()> {
+    match command {
+        Command::Parse { input, trivia } => {
+            let file = input.to_string_lossy();
+            let tokens = read_lisp_spanned(&mut open(&input)?, Some(&file))?;
+            for token in tokens
+                .into_iter()
+                .filter(|spanned| trivia || !spanned.token.is_comment())
+            {
                 println!("{}", serde_json::to_string(&token)?);
             }
         }
+        Command::Unparse { input } => {
+            for (number, line) in open(&input)?.lines().enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                // Tagged enums lose serde_json's position, so add the line.
+                let token: Token = serde_json::from_str(&line).map_err(|err| {
+                    let message = format!("{} at line {}", err, number + 1);
+                    <serde_json::Error as serde::de::Error>::custom(message)
+                })?;
+                println!("{}", token);
+            }
+        }
+        Command::Run { input, all } => {
+            let file = input.to_string_lossy();
+            let mut last = None;
+            for spanned in Reader::new(open(&input)?, Some(&file)) {
+                let spanned = spanned?;
+                if spanned.token.is_comment() {
+                    continue;
+                }
+                let value = interp
+                    .eval_toplevel(&spanned.token)
+                    .map_err(|err| Error::InForm(Box::new(spanned.span), Box::new(err)))?;
+                let printed = interp.print(&value).to_string();
+                if all {
+                    println!("{}", printed);
+                }
+                last = Some(printed);
+            }
+            if let (false, Some(printed)) = (all, last) {
+                println!("{}", printed);
+            }
+        }
+        Command::Repl => repl(interp, &mut io::stdin().lock(), &mut io::stdout())?,
     };
-
     Ok(())
 }
 
+/// Opens the file at `input`, or standard input if it is `-`.
+fn open(input: &Path) -> Result<Box<dyn BufRead>> {
+    if input.as_os_str() == "-" {
+        Ok(Box::new(io::stdin().lock()))
+    } else {
+        Ok(Box::new(BufReader::new(File::open(input)?)))
+    }
+}
+
 /// A small lisp implementation.
 ///
 /// I made this while working through the book *Lisp in Small Pieces*.
@@ -37,16 +125,47 @@ struct Cli {
     #[clap(flatten)]
     verbose: Verbosity,
 
+    /// Print garbage collector statistics on exit.
+    #[arg(long, global = true)]
+    gc_stats: bool,
+
+    /// Evaluate as a Lisp-2, with separate namespaces for functions and
+    /// variables.
+    #[arg(long, global = true)]
+    lisp2: bool,
+
     #[command(subcommand)]
     command: Command,
 }
 
 #[derive(Debug, Subcommand)]
 enum Command {
-    /// Parse a file and print out the tokens.
+    /// Parse a file and print out the tokens and their locations.
     Parse {
-        /// The input file to read.
+        /// The input file to read, or `-` for standard input.
         #[arg(short, long)]
         input: PathBuf,
+
+        /// Also print the comments, which are otherwise skipped.
+        #[arg(long)]
+        trivia: bool,
+    },
+    /// Convert tokens in JSON, as printed by `parse`, back into source.
+    Unparse {
+        /// The input file to read, or `-` for standard input.
+        #[arg(short, long)]
+        input: PathBuf,
+    },
+    /// Evaluate a file and print the value of its last form.
+    Run {
+        /// The input file to read, or `-` for standard input.
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Print the value of every top-level form.
+        #[arg(short, long)]
+        all: bool,
     },
+    /// Start an interactive session.
+    Repl,
 }
@@ -0,0 +1,479 @@
+//! The numeric tower.
+//!
+//! Exact integers are `i64`s until an operation overflows, when they are
+//! promoted to [`BigInt`]s; results that fit in an `i64` again are demoted.
+//! Exact rationals are always in lowest terms, and one whose denominator is 1
+//! is an integer. Any operation involving a float is inexact.
+
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{Signed, ToPrimitive};
+
+use crate::error::{Error, Result};
+use crate::printer::write_float;
+use crate::value::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Number {
+    Integer(i64),
+    /// An integer too large for an `i64`.
+    Big(BigInt),
+    /// A rational in lowest terms whose denominator is not 1.
+    Rational(BigRational),
+    Float(f64),
+}
+
+impl From<i64> for Number {
+    fn from(value: i64) -> Self {
+        Number::Integer(value)
+    }
+}
+
+impl From<f64> for Number {
+    fn from(value: f64) -> Self {
+        Number::Float(value)
+    }
+}
+
+impl From<BigInt> for Number {
+    fn from(value: BigInt) -> Self {
+        match value.to_i64() {
+            Some(value) => Number::Integer(value),
+            None => Number::Big(value),
+        }
+    }
+}
+
+impl From<BigRational> for Number {
+    /// Reduces `value` to lowest terms, demoting it to an integer if its
+    /// denominator becomes 1.
+    fn from(value: BigRational) -> Self {
+        let value = value.reduced();
+        if value.is_integer() {
+            value.to_integer().into()
+        } else {
+            Number::Rational(value)
+        }
+    }
+}
+
+impl From<Number> for Value {
+    fn from(value: Number) -> Self {
+        match value {
+            Number::Integer(value) => Value::Integer(value),
+            Number::Big(value) => Value::BigInteger(Rc::new(value)),
+            Number::Rational(value) => Value::Rational(Rc::new(value)),
+            Number::Float(value) => Value::Float(value),
+        }
+    }
+}
+
+impl Number {
+    /// Returns the number a value holds, if it is one.
+    pub fn from_value(value: &Value) -> Option<Number> {
+        match value {
+            Value::Integer(value) => Some(Number::Integer(*value)),
+            Value::BigInteger(value) => Some(Number::Big(value.as_ref().clone())),
+            Value::Rational(value) => Some(Number::Rational(value.as_ref().clone())),
+            Value::Float(value) => Some(Number::Float(*value)),
+            _ => None,
+        }
+    }
+
+    pub fn is_exact(&self) -> bool {
+        !matches!(self, Number::Float(_))
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Number::Integer(value) => *value as f64,
+            Number::Big(value) => value.to_f64().unwrap_or(f64::NAN),
+            Number::Rational(value) => value.to_f64().unwrap_or(f64::NAN),
+            Number::Float(value) => *value,
+        }
+    }
+
+    /// Converts an exact number to a rational.
+    fn to_ratio(&self) -> BigRational {
+        match self {
+            Number::Integer(value) => BigRational::from_integer((*value).into()),
+            Number::Big(value) => BigRational::from_integer(value.clone()),
+            Number::Rational(value) => value.clone(),
+            Number::Float(_) => unreachable!("floats are not exact"),
+        }
+    }
+
+    /// Converts an exact integer to a `BigInt`.
+    fn to_bigint(&self) -> BigInt {
+        match self {
+            Number::Integer(value) => (*value).into(),
+            Number::Big(value) => value.clone(),
+            _ => unreachable!("not an exact integer"),
+        }
+    }
+
+    /// Applies a binary operation at the lowest level of the tower that can
+    /// represent both operands.
+    fn combine(
+        &self,
+        other: &Number,
+        small: fn(i64, i64) -> Option<i64>,
+        big: fn(&BigInt, &BigInt) -> BigInt,
+        ratio: fn(&BigRational, &BigRational) -> BigRational,
+        float: fn(f64, f64) -> f64,
+    ) -> Number {
+        match (self, other) {
+            (Number::Integer(a), Number::Integer(b)) => match small(*a, *b) {
+                Some(value) => Number::Integer(value),
+                None => big(&(*a).into(), &(*b).into()).into(),
+            },
+            (Number::Float(_), _) | (_, Number::Float(_)) => {
+                Number::Float(float(self.to_f64(), other.to_f64()))
+            }
+            (Number::Rational(_), _) | (_, Number::Rational(_)) => {
+                ratio(&self.to_ratio(), &other.to_ratio()).into()
+            }
+            _ => big(&self.to_bigint(), &other.to_bigint()).into(),
+        }
+    }
+
+    pub fn add(&self, other: &Number) -> Number {
+        self.combine(
+            other,
+            i64::checked_add,
+            |a, b| a + b,
+            |a, b| a + b,
+            |a, b| a + b,
+        )
+    }
+
+    pub fn sub(&self, other: &Number) -> Number {
+        self.combine(
+            other,
+            i64::checked_sub,
+            |a, b| a - b,
+            |a, b| a - b,
+            |a, b| a - b,
+        )
+    }
+
+    pub fn mul(&self, other: &Number) -> Number {
+        self.combine(
+            other,
+            i64::checked_mul,
+            |a, b| a * b,
+            |a, b| a * b,
+            |a, b| a * b,
+        )
+    }
+
+    /// Divides, failing if the divisor is an exact zero.
+    pub fn div(&self, other: &Number) -> Result<Number> {
+        if other.is_exact_zero() {
+            return Err(Error::DivisionByZero("/".to_string()));
+        }
+        Ok(match (self, other) {
+            (Number::Float(_), _) | (_, Number::Float(_)) => {
+                Number::Float(self.to_f64() / other.to_f64())
+            }
+            _ => (self.to_ratio() / other.to_ratio()).into(),
+        })
+    }
+
+    /// Applies an integer division operation. Integral floats are accepted,
+    /// giving a float.
+    fn integer_division(
+        &self,
+        other: &Number,
+        name: &str,
+        small: fn(i64, i64) -> Option<i64>,
+        big: fn(&BigInt, &BigInt) -> BigInt,
+        float: fn(f64, f64) -> f64,
+    ) -> Result<Number> {
+        self.check_integer(name)?;
+        other.check_integer(name)?;
+        if other.is_zero() {
+            return Err(Error::DivisionByZero(name.to_string()));
+        }
+        Ok(match (self, other) {
+            (Number::Float(_), _) | (_, Number::Float(_)) => {
+                Number::Float(float(self.to_f64(), other.to_f64()))
+            }
+            _ => self.combine(
+                other,
+                small,
+                big,
+                |_, _| unreachable!(),
+                |_, _| unreachable!(),
+            ),
+        })
+    }
+
+    /// Divides, truncating towards zero.
+    pub fn quotient(&self, other: &Number) -> Result<Number> {
+        self.integer_division(
+            other,
+            "quotient",
+            i64::checked_div,
+            |a, b| a / b,
+            |a, b| (a / b).trunc(),
+        )
+    }
+
+    /// The remainder of [`Number::quotient`], which has the sign of the
+    /// dividend.
+    pub fn remainder(&self, other: &Number) -> Result<Number> {
+        self.integer_division(
+            other,
+            "remainder",
+            i64::checked_rem,
+            |a, b| a % b,
+            |a, b| a % b,
+        )
+    }
+
+    /// The remainder of flooring division, which has the sign of the
+    /// divisor.
+    pub fn modulo(&self, other: &Number) -> Result<Number> {
+        self.integer_division(
+            other,
+            "modulo",
+            |a, b| {
+                a.checked_rem(b).map(|r| {
+                    if r != 0 && (r < 0) != (b < 0) {
+                        r + b
+                    } else {
+                        r
+                    }
+                })
+            },
+            |a, b| a.mod_floor(b),
+            |a, b| a - b * (a / b).floor(),
+        )
+    }
+
+    pub fn gcd(&self, other: &Number) -> Result<Number> {
+        self.integer_op(other, "gcd", |a, b| a.gcd(b))
+    }
+
+    pub fn lcm(&self, other: &Number) -> Result<Number> {
+        self.integer_op(other, "lcm", |a, b| a.lcm(b))
+    }
+
+    /// Applies an operation on integers that cannot fail, such as `gcd`.
+    fn integer_op(
+        &self,
+        other: &Number,
+        name: &str,
+        op: fn(&BigInt, &BigInt) -> BigInt,
+    ) -> Result<Number> {
+        self.check_integer(name)?;
+        other.check_integer(name)?;
+        if self.is_exact() && other.is_exact() {
+            Ok(op(&self.to_bigint(), &other.to_bigint()).into())
+        } else {
+            let a = Number::Float(self.to_f64()).to_exact()?;
+            let b = Number::Float(other.to_f64()).to_exact()?;
+            Ok(Number::from(op(&a.to_bigint(), &b.to_bigint())).to_inexact())
+        }
+    }
+
+    pub fn abs(&self) -> Number {
+        match self {
+            Number::Integer(value) => match value.checked_abs() {
+                Some(value) => Number::Integer(value),
+                None => BigInt::from(*value).abs().into(),
+            },
+            Number::Big(value) => value.abs().into(),
+            Number::Rational(value) => value.abs().into(),
+            Number::Float(value) => Number::Float(value.abs()),
+        }
+    }
+
+    pub fn floor(&self) -> Number {
+        self.round_with(BigRational::floor, f64::floor)
+    }
+
+    pub fn ceiling(&self) -> Number {
+        self.round_with(BigRational::ceil, f64::ceil)
+    }
+
+    pub fn truncate(&self) -> Number {
+        self.round_with(BigRational::trunc, f64::trunc)
+    }
+
+    /// Rounds to the nearest integer, and to the even one when halfway
+    /// between two.
+    pub fn round(&self) -> Number {
+        self.round_with(
+            |value| {
+                let floor = value.floor();
+                let double = (value - &floor) * BigInt::from(2);
+                match double.cmp(&BigRational::from_integer(1.into())) {
+                    Ordering::Less => floor,
+                    Ordering::Greater => floor + BigInt::from(1),
+                    Ordering::Equal if floor.to_integer().is_even() => floor,
+                    Ordering::Equal => floor + BigInt::from(1),
+                }
+            },
+            f64::round_ties_even,
+        )
+    }
+
+    fn round_with(&self, ratio: fn(&BigRational) -> BigRational, float: fn(f64) -> f64) -> Number {
+        match self {
+            Number::Rational(value) => ratio(value).into(),
+            Number::Float(value) => Number::Float(float(*value)),
+            integer => integer.clone(),
+        }
+    }
+
+    pub fn to_inexact(&self) -> Number {
+        Number::Float(self.to_f64())
+    }
+
+    /// Converts a float to the exact rational it represents.
+    pub fn to_exact(&self) -> Result<Number> {
+        match self {
+            Number::Float(value) => BigRational::from_float(*value)
+                .map(Number::from)
+                .ok_or_else(|| {
+                    let text = self.to_string_radix(10).unwrap_or_default();
+                    Error::WrongType(format!("{} has no exact value", text))
+                }),
+            exact => Ok(exact.clone()),
+        }
+    }
+
+    /// Whether the number is an integer, exact or not.
+    pub fn is_integer(&self) -> bool {
+        match self {
+            Number::Integer(_) | Number::Big(_) => true,
+            Number::Rational(_) => false,
+            Number::Float(value) => value.fract() == 0.0,
+        }
+    }
+
+    fn check_integer(&self, name: &str) -> Result<()> {
+        if self.is_integer() {
+            Ok(())
+        } else {
+            Err(Error::WrongType(format!(
+                "{} expects integers, got {}",
+                name,
+                self.to_string_radix(10).unwrap_or_default()
+            )))
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        match self {
+            Number::Integer(value) => *value == 0,
+            Number::Big(_) | Number::Rational(_) => false,
+            Number::Float(value) => *value == 0.0,
+        }
+    }
+
+    fn is_exact_zero(&self) -> bool {
+        matches!(self, Number::Integer(0))
+    }
+
+    /// Formats the number in `radix`, which must be 10 for floats.
+    pub fn to_string_radix(&self, radix: u32) -> Option<String> {
+        match self {
+            Number::Integer(value) => Some(BigInt::from(*value).to_str_radix(radix)),
+            Number::Big(value) => Some(value.to_str_radix(radix)),
+            Number::Rational(value) => Some(format!(
+                "{}/{}",
+                value.numer().to_str_radix(radix),
+                value.denom().to_str_radix(radix)
+            )),
+            Number::Float(value) if radix == 10 => {
+                let mut text = String::new();
+                write_float(&mut text, *value).ok()?;
+                Some(text)
+            }
+            Number::Float(_) => None,
+        }
+    }
+
+    /// Compares two numbers by value, regardless of exactness. Returns `None`
+    /// if either is NaN.
+    pub fn compare(&self, other: &Number) -> Option<Ordering> {
+        match (self, other) {
+            (Number::Integer(a), Number::Integer(b)) => Some(a.cmp(b)),
+            (Number::Float(_), _) | (_, Number::Float(_)) => {
+                self.to_f64().partial_cmp(&other.to_f64())
+            }
+            _ => Some(self.to_ratio().cmp(&other.to_ratio())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_promote_on_overflow() {
+        let sum = Number::Integer(i64::MAX).add(&Number::Integer(1));
+        assert_eq!(sum, Number::Big(BigInt::from(i64::MAX) + 1));
+    }
+
+    #[test]
+    fn test_demote_when_small() {
+        let big = Number::Integer(i64::MAX).add(&Number::Integer(1));
+        assert_eq!(big.sub(&Number::Integer(2)), Number::Integer(i64::MAX - 1));
+    }
+
+    #[test]
+    fn test_rational_normalized() {
+        let half = Number::from(BigRational::new(2.into(), (-4).into()));
+        assert_eq!(
+            half,
+            Number::Rational(BigRational::new_raw((-1).into(), 2.into()))
+        );
+        assert_eq!(half.add(&half), Number::Integer(-1));
+    }
+
+    #[test]
+    fn test_float_contagion() {
+        let half = Number::from(BigRational::new(1.into(), 2.into()));
+        assert_eq!(half.mul(&Number::Float(3.0)), Number::Float(1.5));
+    }
+
+    #[test]
+    fn test_integer_division_overflow() {
+        let min = Number::Integer(i64::MIN);
+        let minus_one = Number::Integer(-1);
+        assert_eq!(
+            min.quotient(&minus_one).unwrap(),
+            Number::Big(-BigInt::from(i64::MIN))
+        );
+        assert_eq!(min.modulo(&minus_one).unwrap(), Number::Integer(0));
+    }
+
+    #[test]
+    fn test_round_half_to_even() {
+        let ratio = |n: i64, d: i64| Number::from(BigRational::new(n.into(), d.into()));
+        assert_eq!(ratio(1, 2).round(), Number::Integer(0));
+        assert_eq!(ratio(3, 2).round(), Number::Integer(2));
+        assert_eq!(ratio(-5, 2).round(), Number::Integer(-2));
+        assert_eq!(ratio(7, 3).round(), Number::Integer(2));
+    }
+
+    #[test]
+    fn test_compare_across_tower() {
+        let third = Number::from(BigRational::new(1.into(), 3.into()));
+        let big = Number::Big(BigInt::from(i64::MAX) * 4);
+        assert_eq!(third.compare(&Number::Float(0.3)), Some(Ordering::Greater));
+        assert_eq!(third.compare(&big), Some(Ordering::Less));
+        assert_eq!(Number::Float(f64::NAN).compare(&third), None);
+    }
+}
@@ -0,0 +1,371 @@
+//! Printing values in Lisp syntax.
+//!
+//! As in Scheme, there are two styles. [`Style::Write`] escapes strings and
+//! characters, so that data made of numbers, strings, characters, symbols,
+//! lists and vectors read back as equal data. [`Style::Display`] prints
+//! strings and characters as their plain contents, for output meant for
+//! people.
+//!
+//! Both styles print a structure that contains itself with datum labels, as
+//! in `#0=(a . #0#)`, so that printing it ends. The reader rejects labels,
+//! so such output cannot be read back; neither can procedures, which print
+//! as `#<procedure>`.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Write};
+
+use crate::heap::{Heap, Object, Ref};
+use crate::symbol::{self, Symbol};
+use crate::token::CHARACTER_NAMES;
+use crate::value::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Write,
+    Display,
+}
+
+/// A value paired with the heap its objects live on, for formatting.
+pub struct Printed<'a> {
+    value: &'a Value,
+    heap: &'a Heap,
+    style: Style,
+}
+
+impl<'a> Printed<'a> {
+    pub fn new(value: &'a Value, heap: &'a Heap, style: Style) -> Self {
+        Printed { value, heap, style }
+    }
+}
+
+impl fmt::Display for Printed<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut printer = Printer {
+            heap: self.heap,
+            style: self.style,
+            cycles: find_cycles(self.heap, self.value),
+            labels: HashMap::new(),
+        };
+        printer.value(f, self.value)
+    }
+}
+
+/// The state of printing one value.
+struct Printer<'a> {
+    heap: &'a Heap,
+    style: Style,
+    /// The objects that are part of a cycle, and so need a label.
+    cycles: HashSet<Ref>,
+    /// The labels given so far.
+    labels: HashMap<Ref, usize>,
+}
+
+impl<'a> Printer<'a> {
+    fn value(&mut self, f: &mut fmt::Formatter<'_>, value: &'a Value) -> fmt::Result {
+        match value {
+            Value::Pair(r) | Value::Vector(r) if self.cycles.contains(r) => {
+                if let Some(label) = self.labels.get(r) {
+                    return write!(f, "#{}#", label);
+                }
+                let label = self.labels.len();
+                self.labels.insert(*r, label);
+                write!(f, "#{}=", label)?;
+                self.object(f, value)
+            }
+            _ => self.object(f, value),
+        }
+    }
+
+    fn object(&mut self, f: &mut fmt::Formatter<'_>, value: &'a Value) -> fmt::Result {
+        match value {
+            Value::Integer(value) => write!(f, "{}", value),
+            Value::BigInteger(value) => write!(f, "{}", value),
+            Value::Rational(value) => write!(f, "{}", value),
+            Value::Float(value) => write_float(f, *value),
+            Value::String(value) => match self.style {
+                Style::Write => write_string(f, value),
+                Style::Display => f.write_str(value),
+            },
+            Value::Symbol(name) => write!(f, "{}", name),
+            Value::Pair(_) => self.list(f, value),
+            Value::EmptyList => write!(f, "()"),
+            Value::Boolean(true) => write!(f, "#t"),
+            Value::Boolean(false) => write!(f, "#f"),
+            Value::Character(c) => match self.style {
+                Style::Write => write_char(f, *c),
+                Style::Display => f.write_char(*c),
+            },
+            Value::Vector(r) => {
+                let items = match self.heap.get(*r) {
+                    Object::Vector(items) => items,
+                    _ => return Err(fmt::Error),
+                };
+                write!(f, "#(")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    self.value(f, item)?;
+                }
+                write!(f, ")")
+            }
+            Value::Procedure(_) => write!(f, "#<procedure>"),
+            Value::Primitive(primitive) => write!(f, "#<primitive {}>", primitive.name),
+            Value::Unassigned => write!(f, "#<unassigned>"),
+        }
+    }
+
+    /// Prints a pair, as a list for as long as its tail is one.
+    fn list(&mut self, f: &mut fmt::Formatter<'_>, value: &'a Value) -> fmt::Result {
+        let heap = self.heap;
+        let (car, cdr) = heap.pair(value).map_err(|_| fmt::Error)?;
+        if let (Value::Symbol(name), Some(datum)) = (car, self.only_item(cdr)) {
+            if let Some(prefix) = abbreviation(*name) {
+                f.write_str(prefix)?;
+                return self.value(f, datum);
+            }
+        }
+        write!(f, "(")?;
+        self.value(f, car)?;
+        let mut cursor = cdr;
+        loop {
+            match cursor {
+                // A labelled tail has to be printed after a dot.
+                Value::Pair(r) if !self.cycles.contains(r) => {
+                    let (car, cdr) = heap.pair(cursor).map_err(|_| fmt::Error)?;
+                    write!(f, " ")?;
+                    self.value(f, car)?;
+                    cursor = cdr;
+                }
+                Value::EmptyList => break,
+                tail => {
+                    write!(f, " . ")?;
+                    self.value(f, tail)?;
+                    break;
+                }
+            }
+        }
+        write!(f, ")")
+    }
+
+    /// The item of a one-item list that can be printed without a label.
+    fn only_item(&self, list: &'a Value) -> Option<&'a Value> {
+        match list {
+            Value::Pair(r) if !self.cycles.contains(r) => match self.heap.pair(list) {
+                Ok((item, Value::EmptyList)) => Some(item),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Finds the pairs and vectors reachable from `value` that are part of a
+/// cycle, with a depth-first search that does not recurse, since lists can
+/// be very long.
+fn find_cycles(heap: &Heap, value: &Value) -> HashSet<Ref> {
+    enum Visit {
+        Enter(Ref),
+        Leave(Ref),
+    }
+    let mut cycles = HashSet::new();
+    // Whether each object seen is still being searched.
+    let mut searching: HashMap<Ref, bool> = HashMap::new();
+    let mut pending: Vec<Visit> = structure(value).map(Visit::Enter).into_iter().collect();
+    while let Some(visit) = pending.pop() {
+        match visit {
+            Visit::Enter(r) => match searching.get(&r) {
+                // Still searching it, so it is one of its own descendants.
+                Some(true) => {
+                    cycles.insert(r);
+                }
+                Some(false) => {}
+                None => {
+                    searching.insert(r, true);
+                    pending.push(Visit::Leave(r));
+                    let children: Vec<&Value> = match heap.get(r) {
+                        Object::Pair(car, cdr) => vec![car, cdr],
+                        Object::Vector(items) => items.iter().collect(),
+                        _ => Vec::new(),
+                    };
+                    pending.extend(children.into_iter().filter_map(structure).map(Visit::Enter));
+                }
+            },
+            Visit::Leave(r) => {
+                searching.insert(r, false);
+            }
+        }
+    }
+    cycles
+}
+
+/// The heap object of a pair or vector.
+fn structure(value: &Value) -> Option<Ref> {
+    match value {
+        Value::Pair(r) | Value::Vector(r) => Some(*r),
+        _ => None,
+    }
+}
+
+/// The prefix that abbreviates a form like `(quote x)`, if there is one.
+pub fn abbreviation(name: Symbol) -> Option<&'static str> {
+    match name {
+        symbol::QUOTE => Some("'"),
+        symbol::QUASIQUOTE => Some("`"),
+        symbol::UNQUOTE => Some(","),
+        symbol::UNQUOTE_SPLICING => Some(",@"),
+        symbol::FUNCTION => Some("#'"),
+        _ => None,
+    }
+}
+
+/// Writes a string in double quotes, escaping it so that the reader reads
+/// back the same string.
+pub fn write_string<W: Write>(out: &mut W, value: &str) -> fmt::Result {
+    out.write_char('"')?;
+    for c in value.chars() {
+        match c {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            '\u{7}' => out.write_str("\\a")?,
+            '\u{8}' => out.write_str("\\b")?,
+            '\0' => out.write_str("\\0")?,
+            c if c.is_control() => write!(out, "\\x{:x};", c as u32)?,
+            c => out.write_char(c)?,
+        }
+    }
+    out.write_char('"')
+}
+
+/// Writes a character as the reader reads it, by name if it has one.
+pub fn write_char<W: Write>(out: &mut W, value: char) -> fmt::Result {
+    match CHARACTER_NAMES.iter().find(|(_, c)| *c == value) {
+        Some((name, _)) => write!(out, "#\\{}", name),
+        None if value.is_control() => write!(out, "#\\x{:x}", value as u32),
+        None => write!(out, "#\\{}", value),
+    }
+}
+
+/// Writes a float so that it reads back as the same float: always with a
+/// point or an exponent, and with R7RS syntax for infinities and NaN.
+pub fn write_float<W: Write>(out: &mut W, value: f64) -> fmt::Result {
+    if value.is_nan() {
+        out.write_str("+nan.0")
+    } else if value.is_infinite() {
+        out.write_str(if value > 0.0 { "+inf.0" } else { "-inf.0" })
+    } else {
+        write!(out, "{:?}", value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::reader::read_lisp;
+    use crate::token::Token;
+
+    /// Reads `source`, writes each datum and checks that the text reads back
+    /// as the same data.
+    fn round_trip(source: &str) -> String {
+        let tokens = read_lisp(&mut source.as_bytes()).unwrap();
+        let written: Vec<String> = tokens.iter().map(Token::to_string).collect();
+        let written = written.join(" ");
+        assert_eq!(read_lisp(&mut written.as_bytes()).unwrap(), tokens);
+        written
+    }
+
+    #[test]
+    fn test_round_trip_tokens() {
+        assert_eq!(
+            round_trip("(a (b . c) \"d\\\"e\\\\\\n\\x1;\" #\\x #\\space 1/3 -0.25 1e300)"),
+            "(a (b . c) \"d\\\"e\\\\\\n\\x1;\" #\\x #\\space 1/3 -0.25 1e300)"
+        );
+        assert_eq!(
+            round_trip("(quote x) `(a ,b ,@c) #'f (quote x y)"),
+            "'x `(a ,b ,@c) #'f (quote x y)"
+        );
+        assert_eq!(round_trip("#(1 #t) +inf.0 -inf.0"), "#(1 #t) +inf.0 -inf.0");
+    }
+
+    #[test]
+    fn test_write_nan() {
+        let token = Token::from_str("+nan.0").unwrap();
+        assert_eq!(token.to_string(), "+nan.0");
+    }
+
+    #[test]
+    fn test_write_and_display() {
+        let mut heap = Heap::default();
+        let value = heap.list(vec![
+            "a \"b\"\n".into(),
+            Value::Character('c'),
+            Value::Character(' '),
+        ]);
+        assert_eq!(
+            value.write(&heap).to_string(),
+            "(\"a \\\"b\\\"\\n\" #\\c #\\space)"
+        );
+        assert_eq!(value.display(&heap).to_string(), "(a \"b\"\n c  )");
+    }
+
+    #[test]
+    fn test_write_quote() {
+        let mut heap = Heap::default();
+        let quoted = heap.list(vec![Value::symbol("quote"), Value::symbol("x")]);
+        let value = heap.list(vec![quoted, Value::symbol("quote")]);
+        assert_eq!(value.write(&heap).to_string(), "('x quote)");
+    }
+
+    #[test]
+    fn test_write_cyclic_list() {
+        let mut heap = Heap::default();
+        let pair = heap.cons(Value::symbol("a"), Value::EmptyList);
+        heap.set_cdr(&pair, pair.clone()).unwrap();
+        assert_eq!(pair.write(&heap).to_string(), "#0=(a . #0#)");
+    }
+
+    #[test]
+    fn test_write_cycle_in_tail() {
+        let mut heap = Heap::default();
+        let tail = heap.list(vec![2.into(), 3.into()]);
+        let list = heap.list_with_tail(vec![1.into()], tail.clone());
+        let last = heap.cdr(&tail).unwrap();
+        heap.set_cdr(&last, tail.clone()).unwrap();
+        assert_eq!(list.write(&heap).to_string(), "(1 . #0=(2 3 . #0#))");
+    }
+
+    #[test]
+    fn test_write_cyclic_vector() {
+        let mut heap = Heap::default();
+        let vector = heap.vector(vec![1.into(), Value::EmptyList]);
+        let list = heap.list(vec![vector.clone()]);
+        if let (Value::Vector(r), Value::Pair(_)) = (&vector, &list) {
+            if let Object::Vector(items) = heap.get_mut(*r) {
+                items[1] = list.clone();
+            }
+        }
+        assert_eq!(vector.write(&heap).to_string(), "#0=#(1 (#0#))");
+    }
+
+    #[test]
+    fn test_write_shared_without_labels() {
+        let mut heap = Heap::default();
+        let shared = heap.list(vec![1.into()]);
+        let value = heap.list(vec![shared.clone(), shared]);
+        assert_eq!(value.write(&heap).to_string(), "((1) (1))");
+    }
+
+    #[test]
+    fn test_write_long_list() {
+        let mut heap = Heap::default();
+        let value = heap.list((0..100_000).map(Value::Integer).collect());
+        assert!(value.write(&heap).to_string().ends_with(" 99999)"));
+    }
+}
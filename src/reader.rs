@@ -1,23 +1,31 @@
 use std::io::BufRead;
+use std::ops::Range;
 use std::str::FromStr;
 
-use nom::Finish;
+use num_rational::BigRational;
 
-use crate::error::{Error, Result};
-use crate::token::Token;
+use crate::error::{Diagnostic, Error, Result};
+use crate::number::Number;
+use crate::token::{Span, Spanned, Token};
 
+mod diagnose;
 mod parser;
+mod stream;
 
-use self::parser::{parse_token, parse_token_list};
+use self::diagnose::{diagnose, Problem};
+use self::parser::{parse_number, parse_token, parse_token_list, Extent, SyntaxError};
+pub use self::stream::Reader;
 
 impl FromStr for Token {
     type Err = Error;
 
     fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
-        match parse_token(s).finish() {
+        match parse_token(s) {
             Ok((_remaining, token)) => Ok(token),
-            Err(nom::error::Error { input, code }) => {
-                Err(Error::ParseError(input.to_string(), code))
+            Err(err) => {
+                let lines = LineIndex::new(s);
+                let problem = explain(s, err, &lines);
+                Err(lines.error(s, None, problem.offset, problem.message))
             }
         }
     }
@@ -35,11 +43,32 @@ impl From<f64> for Token {
     }
 }
 
-impl From<(i64, i64)> for Token {
-    fn from(value: (i64, i64)) -> Self {
-        Token::Rational {
-            numerator: value.0,
-            denominator: value.1,
+/// Builds the token for a numerator and denominator, reduced to lowest terms.
+/// Fails if the denominator is zero.
+impl TryFrom<(i64, i64)> for Token {
+    type Error = Error;
+
+    fn try_from((numerator, denominator): (i64, i64)) -> Result<Self> {
+        if denominator == 0 {
+            return Err(Error::DivisionByZero(format!("{}/{}", numerator, denominator)));
+        }
+        Ok(Number::from(BigRational::new(numerator.into(), denominator.into())).into())
+    }
+}
+
+impl From<Number> for Token {
+    fn from(value: Number) -> Self {
+        match value {
+            Number::Integer(value) => Token::Integer { value },
+            Number::Big(value) => Token::BigInteger { value },
+            Number::Rational(value) => {
+                let (numerator, denominator) = value.into();
+                Token::Rational {
+                    numerator,
+                    denominator,
+                }
+            }
+            Number::Float(value) => Token::Float { value },
         }
     }
 }
@@ -84,12 +113,198 @@ impl From<&[Token]> for Token {
     }
 }
 
+/// Whether `text` reads as exactly `token` and nothing more.
+pub(crate) fn reads_as(text: &str, token: &Token) -> bool {
+    matches!(parse_token(text), Ok(("", read)) if read == *token)
+}
+
+/// Whether `name` reads back as the symbol of that name, rather than as a
+/// number, a boolean or several tokens, say.
+pub(crate) fn is_symbol_name(name: &str) -> bool {
+    matches!(parse_token(name), Ok(("", Token::Symbol { value })) if value.name() == name)
+}
+
 pub fn read_lisp<R: BufRead>(reader: &mut R) -> Result<Vec<Token>> {
+    Ok(read_lisp_spanned(reader, None)?
+        .into_iter()
+        .map(|spanned| spanned.token)
+        .collect())
+}
+
+/// Reads every token from `reader`, recording where each was found, along
+/// with the data inside it. `file` is only used to label the spans and
+/// diagnostics.
+///
+/// The whole input is read before parsing starts; see [`Reader`] to read one
+/// datum at a time.
+pub fn read_lisp_spanned<R: BufRead>(reader: &mut R, file: Option<&str>) -> Result<Vec<Spanned>> {
     let mut buffer = String::new();
     reader.read_to_string(&mut buffer)?;
-    parse_token_list(&buffer)
-        .map(|(_, token)| token)
-        .map_err(Error::from)
+    let lines = LineIndex::new(&buffer);
+    let tokens = parse(&buffer, &lines)
+        .map_err(|problem| lines.error(&buffer, file, problem.offset, problem.message))?;
+    Ok(tokens
+        .into_iter()
+        .map(|(token, extent)| Spanned {
+            token,
+            span: lines.locate(&buffer, file, &extent),
+        })
+        .collect())
+}
+
+/// Reads `text` as a number, as `string->number` does: digits are in
+/// `radix` unless the text has a radix prefix, and the whole text must be a
+/// number.
+pub fn read_number(text: &str, radix: u32) -> Option<Number> {
+    parse_number(text, radix)
+}
+
+/// Parses every token in `input`, or finds the problem that prevents it.
+fn parse(input: &str, lines: &LineIndex) -> std::result::Result<Vec<(Token, Extent)>, Problem> {
+    match parse_token_list(input) {
+        Ok(("", tokens)) => Ok(tokens),
+        // `parse_token_list` stops at the first token it cannot parse.
+        Ok((remaining, _)) => Err(diagnose(input, input.len() - remaining.len(), lines)),
+        Err(err) => Err(explain(input, err, lines)),
+    }
+}
+
+/// Turns a parser error into a problem, using its message if it has one.
+fn explain(input: &str, err: nom::Err<SyntaxError<&str>>, lines: &LineIndex) -> Problem {
+    match err {
+        nom::Err::Error(err) | nom::Err::Failure(err) => {
+            let offset = input.len() - err.input.len();
+            match err.message {
+                Some(message) => Problem::invalid(offset, message),
+                None => diagnose(input, offset, lines),
+            }
+        }
+        nom::Err::Incomplete(_) => unreachable!("the parsers only take complete input"),
+    }
+}
+
+/// How many bytes apart [`LineIndex`] records character counts.
+const CHECKPOINT: usize = 256;
+
+/// Counts the UTF-8 characters that start in `bytes`.
+fn count_chars(bytes: &[u8]) -> usize {
+    bytes.iter().filter(|&&byte| byte & 0xc0 != 0x80).count()
+}
+
+/// The byte offsets at which each line of a source string starts.
+///
+/// The source may be a piece of a larger input that starts at the beginning
+/// of a line, in which case positions are reported in the larger input.
+struct LineIndex {
+    starts: Vec<usize>,
+    /// The number of characters before every [`CHECKPOINT`]th byte, so that
+    /// finding a column does not mean counting along the whole line.
+    chars: Vec<usize>,
+    /// The byte offset and line number of the start of the source.
+    first_offset: usize,
+    first_line: usize,
+}
+
+impl LineIndex {
+    fn new(source: &str) -> Self {
+        Self::starting_at(source, 0, 1)
+    }
+
+    /// Indexes `source`, which starts at byte `offset` and on line `line` of
+    /// the input.
+    fn starting_at(source: &str, offset: usize, line: usize) -> Self {
+        let starts: Vec<usize> = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        let chars = std::iter::once(0)
+            .chain(
+                source
+                    .as_bytes()
+                    .chunks(CHECKPOINT)
+                    .scan(0, |count, chunk| {
+                        *count += count_chars(chunk);
+                        Some(*count)
+                    }),
+            )
+            .collect();
+        LineIndex {
+            starts,
+            chars,
+            first_offset: offset,
+            first_line: line,
+        }
+    }
+
+    /// Converts a byte offset into a 1-based line and column.
+    fn position(&self, source: &str, offset: usize) -> (usize, usize) {
+        let line = match self.starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next) => next - 1,
+        };
+        let column =
+            self.chars_before(source, offset) - self.chars_before(source, self.starts[line]);
+        (self.first_line + line, column + 1)
+    }
+
+    /// Counts the characters in `source` before byte `offset`.
+    fn chars_before(&self, source: &str, offset: usize) -> usize {
+        let checkpoint = offset / CHECKPOINT;
+        self.chars[checkpoint] + count_chars(&source.as_bytes()[checkpoint * CHECKPOINT..offset])
+    }
+
+    /// Locates the bytes in `range`.
+    fn span(&self, source: &str, file: Option<&str>, range: Range<usize>) -> Span {
+        let (line, column) = self.position(source, range.start);
+        let (end_line, end_column) = self.position(source, range.end);
+        Span {
+            file: file.map(str::to_string),
+            start: self.first_offset + range.start,
+            end: self.first_offset + range.end,
+            line,
+            column,
+            end_line,
+            end_column,
+            items: Vec::new(),
+        }
+    }
+
+    /// Locates a parsed token and the data inside it.
+    fn locate(&self, source: &str, file: Option<&str>, extent: &Extent) -> Span {
+        Span {
+            items: extent
+                .items
+                .iter()
+                .map(|item| self.locate(source, file, item))
+                .collect(),
+            ..self.span(source, file, extent.range(source))
+        }
+    }
+
+    /// Builds a read error pointing at `offset`.
+    fn error(&self, source: &str, file: Option<&str>, offset: usize, message: String) -> Error {
+        Error::ReadError(Box::new(self.diagnostic(source, file, offset, message)))
+    }
+
+    fn diagnostic(
+        &self,
+        source: &str,
+        file: Option<&str>,
+        offset: usize,
+        message: String,
+    ) -> Diagnostic {
+        let (line, column) = self.position(source, offset);
+        let start = self.starts[line - self.first_line];
+        let end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |end| start + end);
+        Diagnostic {
+            message,
+            file: file.map(str::to_string),
+            line,
+            column,
+            source_line: source[start..end].trim_end_matches('\r').to_string(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,152 @@
+//! Explains why the parser stopped before the end of its input.
+//!
+//! The nom parsers only report that none of their alternatives matched,
+//! which says little about what is actually wrong. Once parsing has failed,
+//! the rest of the input is scanned again here with a much simpler model of
+//! the syntax (parentheses, strings and comments) to find the problem.
+
+use super::LineIndex;
+
+/// The characters the string parser accepts after a `\`, including the
+/// whitespace of a line continuation.
+const ESCAPES: &str = "\\\"'nrtab0xu \t\n\r";
+
+/// The prefixes that apply to the datum after them, longest first.
+const PREFIXES: [&str; 6] = ["#;", "#'", ",@", "'", "`", ","];
+
+/// A problem found in the input, at a byte offset.
+pub(super) struct Problem {
+    pub offset: usize,
+    pub message: String,
+    /// Whether more input could fix the problem.
+    pub incomplete: bool,
+}
+
+impl Problem {
+    pub fn invalid(offset: usize, message: String) -> Self {
+        Problem {
+            offset,
+            message,
+            incomplete: false,
+        }
+    }
+
+    fn incomplete(offset: usize, message: String) -> Self {
+        Problem {
+            offset,
+            message,
+            incomplete: true,
+        }
+    }
+}
+
+/// Scans `source` from the byte offset `start`, where the parser gave up.
+pub(super) fn diagnose(source: &str, start: usize, lines: &LineIndex) -> Problem {
+    let at = |offset: usize| {
+        let (line, column) = lines.position(source, offset);
+        format!("{}:{}", line, column)
+    };
+    let mut open = Vec::new();
+    let mut chars = source[start..]
+        .char_indices()
+        .map(|(i, c)| (start + i, c))
+        .peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '(' => open.push(i),
+            '#' => match chars.peek() {
+                Some((_, '|')) => match block_comment_len(&source[i..]) {
+                    Some(len) => {
+                        chars.by_ref().find(|&(j, _)| j == i + len - 1);
+                    }
+                    None => {
+                        return Problem::incomplete(
+                            i,
+                            format!("unterminated block comment starting at {}", at(i)),
+                        )
+                    }
+                },
+                // Not the start of a line comment.
+                Some((_, ';')) => {
+                    chars.next();
+                }
+                // A character such as `#\(` or `#\"`.
+                Some((_, '\\')) => {
+                    chars.next();
+                    chars.next();
+                }
+                _ => {}
+            },
+            ')' if open.pop().is_none() => {
+                return Problem::invalid(i, "unexpected `)` with no matching `(`".to_string());
+            }
+            ';' => {
+                chars.by_ref().find(|&(_, c)| c == '\n');
+            }
+            '"' => loop {
+                match chars.next() {
+                    None => {
+                        return Problem::incomplete(
+                            i,
+                            format!("unterminated string starting at {}", at(i)),
+                        )
+                    }
+                    Some((_, '"')) => break,
+                    Some((j, '\\')) => match chars.next() {
+                        None => {
+                            return Problem::incomplete(
+                                i,
+                                format!("unterminated string starting at {}", at(i)),
+                            )
+                        }
+                        Some((_, e)) if ESCAPES.contains(e) => {}
+                        Some((_, e)) => {
+                            return Problem::invalid(
+                                j,
+                                format!("invalid escape `\\{}` in string", e.escape_debug()),
+                            )
+                        }
+                    },
+                    Some(_) => {}
+                }
+            },
+            _ => {}
+        }
+    }
+    let trimmed = source[start..].trim_end();
+    match open.pop() {
+        Some(i) => Problem::incomplete(i, format!("unclosed `(` opened at {}", at(i))),
+        None => match PREFIXES.iter().find(|prefix| trimmed.ends_with(*prefix)) {
+            Some(prefix) => Problem::incomplete(
+                start + trimmed.len() - prefix.len(),
+                format!("`{}` is not followed by a datum", prefix),
+            ),
+            None => {
+                let offset = start + (source[start..].len() - source[start..].trim_start().len());
+                Problem::invalid(offset, "cannot read this form".to_string())
+            }
+        },
+    }
+}
+
+/// The length in bytes of the nested block comment at the start of `text`,
+/// or `None` if it never ends.
+fn block_comment_len(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = 0;
+    while i < text.len() {
+        if text[i..].starts_with("#|") {
+            depth += 1;
+            i += 2;
+        } else if text[i..].starts_with("|#") {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return Some(i);
+            }
+        } else {
+            i += text[i..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    None
+}
@@ -1,30 +1,135 @@
+use std::ops::Range;
 
 use nom::branch::alt;
-use nom::bytes::complete::{escaped_transform, is_not, tag};
-use nom::character::complete::{char, digit1, multispace0, multispace1, none_of};
-use nom::combinator::{map, map_res, opt, value};
-use nom::error::ErrorKind;
-use nom::multi::{many0, many1, separated_list0};
-use nom::sequence::{delimited, pair, preceded, tuple};
-use nom::Err;
-use nom::{IResult, Parser};
-
-use crate::token::Token;
-
-pub fn parse_token_list(input: &str) -> IResult<&str, Vec<Token>> {
-    delimited(
+use nom::bytes::complete::{is_not, tag, tag_no_case, take_while1};
+use nom::character::complete::{
+    char, digit0, digit1, multispace0, multispace1, none_of, one_of,
+};
+use nom::combinator::{
+    all_consuming, map, map_opt, not, opt, recognize, success, value, verify,
+};
+use nom::error::{ErrorKind, ParseError};
+use nom::multi::{many0, many0_count, many1};
+use nom::sequence::{delimited, pair, preceded, separated_pair, terminated, tuple};
+use nom::{Err, Parser};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::Zero;
+
+use crate::number::Number;
+use crate::symbol::{self, Symbol};
+use crate::token::{Token, CHARACTER_NAMES};
+
+/// The error type of the parsers. Most errors only mean that a parser did
+/// not match and the next alternative should be tried. Input that is
+/// certainly wrong fails with a message instead, which stops parsing.
+#[derive(Debug, PartialEq)]
+pub struct SyntaxError<I> {
+    pub input: I,
+    pub message: Option<String>,
+}
+
+impl<I> SyntaxError<I> {
+    fn failure(input: I, message: impl Into<String>) -> Err<Self> {
+        Err::Failure(SyntaxError {
+            input,
+            message: Some(message.into()),
+        })
+    }
+}
+
+impl<I> ParseError<I> for SyntaxError<I> {
+    fn from_error_kind(input: I, _kind: ErrorKind) -> Self {
+        SyntaxError {
+            input,
+            message: None,
+        }
+    }
+
+    fn append(_input: I, _kind: ErrorKind, other: Self) -> Self {
+        other
+    }
+}
+
+type IResult<I, O> = nom::IResult<I, O, SyntaxError<I>>;
+
+/// Where a token and the data inside it were parsed from.
+///
+/// A parser only sees the input from where it starts, so offsets are
+/// counted back from the end of the input, which every parser shares.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Extent {
+    start: usize,
+    end: usize,
+    /// The data inside the token, as listed for [`crate::token::Span`].
+    pub items: Vec<Extent>,
+}
+
+impl Extent {
+    /// The byte range in `input`, which must end where the parsed input did.
+    pub fn range(&self, input: &str) -> Range<usize> {
+        input.len() - self.start..input.len() - self.end
+    }
+}
+
+/// Parses a sequence of tokens, each with its extent.
+pub fn parse_token_list(input: &str) -> IResult<&str, Vec<(Token, Extent)>> {
+    terminated(
+        many0(preceded(multispace0, located_token)),
         multispace0,
-        separated_list0(multispace1, parse_token),
-        multispace0
     )(input)
 }
 
+/// Parses the token after any whitespace, with its extent, or `None` if
+/// there is only whitespace left.
+pub fn parse_next_token(input: &str) -> IResult<&str, Option<(Token, Extent)>> {
+    let (start, _) = multispace0(input)?;
+    if start.is_empty() {
+        return Ok((start, None));
+    }
+    map(located_token, Some)(start)
+}
+
+/// Parses a datum or, since they are kept at the top level, a comment.
 pub fn parse_token(input: &str) -> IResult<&str, Token> {
+    map(located_token, |(token, _)| token)(input)
+}
+
+fn located_token(input: &str) -> IResult<&str, (Token, Extent)> {
     alt((
-        comment, cons_list, nil, dot, rational, float, integer, string, sharp_quote, quote, symbol,
+        located(atom(alt((comment, block_comment)))),
+        located(datum_comment),
+        datum,
+        located(atom(dot)),
     ))(input)
 }
 
+fn datum(input: &str) -> IResult<&str, (Token, Extent)> {
+    located(alt((
+        cons_list, vector, sharp_quote, quote, quasiquote, unquote,
+        atom(alt((nil, boolean, character, number, string, datum_label, symbol))),
+    )))(input)
+}
+
+/// Records the extent of what `parser` parses, given the extents of the
+/// data inside it.
+fn located<'a>(
+    mut parser: impl FnMut(&'a str) -> IResult<&'a str, (Token, Vec<Extent>)>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, (Token, Extent)> {
+    move |input| {
+        let (rest, (token, items)) = parser(input)?;
+        let extent = Extent { start: input.len(), end: rest.len(), items };
+        Ok((rest, (token, extent)))
+    }
+}
+
+/// Wraps a parser for a token with no data inside it.
+fn atom<'a>(
+    parser: impl FnMut(&'a str) -> IResult<&'a str, Token>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, (Token, Vec<Extent>)> {
+    map(parser, |token| (token, Vec::new()))
+}
+
 fn comment(input: &str) -> IResult<&str, Token> {
     map(
         pair(
@@ -38,39 +143,156 @@ fn comment(input: &str) -> IResult<&str, Token> {
     )(input)
 }
 
-fn list_item(input: &str) -> IResult<&str, Token> {
-    alt((cons_list, nil, rational, float, integer, string, symbol))(input)
+/// Parses a `#| ... |#` comment. Block comments nest, so the comment only
+/// ends at the `|#` matching its own `#|`.
+fn block_comment(input: &str) -> IResult<&str, Token> {
+    let (body, _) = tag("#|")(input)?;
+    let mut depth = 1;
+    let mut rest = body;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("|#") {
+            depth -= 1;
+            if depth == 0 {
+                let comment = &body[..body.len() - rest.len()];
+                return Ok((after, Token::BlockComment { comment: comment.to_string() }));
+            }
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("#|") {
+            depth += 1;
+            rest = after;
+        } else {
+            let mut chars = rest.chars();
+            chars.next();
+            rest = chars.as_str();
+        }
+    }
+    // Unterminated; `diagnose` explains where the comment started.
+    Err(Err::Error(SyntaxError::from_error_kind(input, ErrorKind::TakeUntil)))
 }
 
-fn cons_list(input: &str) -> IResult<&str, Token> {
-    map(
+/// Parses `#;` and the datum it comments out.
+fn datum_comment(input: &str) -> IResult<&str, (Token, Vec<Extent>)> {
+    let (rest, _) = pair(tag("#;"), atmosphere)(input)?;
+    match datum(rest) {
+        Ok((rest, (datum, extent))) => {
+            Ok((rest, (Token::DatumComment { datum: Box::new(datum) }, vec![extent])))
+        }
+        Err(Err::Error(_)) if rest.starts_with(')') => {
+            Err(SyntaxError::failure(input, "`#;` is not followed by a datum"))
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Skips whitespace and comments between the data of a list.
+fn atmosphere(input: &str) -> IResult<&str, ()> {
+    value(
+        (),
+        many0_count(alt((
+            multispace1,
+            recognize(comment),
+            recognize(block_comment),
+            recognize(datum_comment),
+        ))),
+    )(input)
+}
+
+fn cons_list(input: &str) -> IResult<&str, (Token, Vec<Extent>)> {
+    map_opt(
         delimited(
             list_start,
-            tuple((
-                separated_list0(multispace1, list_item),
-                opt(tuple((multispace1, dot, multispace1, list_item))),
-            )),
-            tuple((multispace0, list_end)),
+            pair(
+                many0(preceded(atmosphere, datum)),
+                opt(preceded(pair(atmosphere, dot), preceded(atmosphere, datum))),
+            ),
+            pair(atmosphere, list_end),
         ),
-        |(head, parsed_tail)| {
-            if head.is_empty() {
-                return Token::EmptyList;
-            }
-            let mut cons_list: Token = head.into();
-            if let Some((_, _, _, tail)) = parsed_tail {
-                cons_list.set_last_tail(tail).unwrap();
+        |(head, tail)| {
+            let (head, mut items): (Vec<Token>, Vec<Extent>) = head.into_iter().unzip();
+            match (head.is_empty(), tail) {
+                (true, None) => Some((Token::EmptyList, items)),
+                // `(. x)` has nothing for the dot to follow.
+                (true, Some(_)) => None,
+                (false, tail) => {
+                    let mut cons_list: Token = head.into();
+                    if let Some((tail, extent)) = tail {
+                        cons_list.set_last_tail(tail).ok()?;
+                        items.push(extent);
+                    }
+                    Some((cons_list, items))
+                }
             }
-            cons_list
         },
     )(input)
 }
 
+/// Parses `#(...)`. Unlike lists, vectors cannot be dotted.
+fn vector(input: &str) -> IResult<&str, (Token, Vec<Extent>)> {
+    map(
+        delimited(
+            tag("#("),
+            many0(preceded(atmosphere, datum)),
+            pair(atmosphere, list_end),
+        ),
+        |items| {
+            let (items, extents) = items.into_iter().unzip();
+            (Token::Vector { items }, extents)
+        },
+    )(input)
+}
+
+fn boolean(input: &str) -> IResult<&str, Token> {
+    map_opt(is_not(DELIMITERS), |atom: &str| match atom {
+        "#t" | "#true" => Some(Token::Boolean { value: true }),
+        "#f" | "#false" => Some(Token::Boolean { value: false }),
+        _ => None,
+    })(input)
+}
+
+/// Parses a character such as `#\a`, `#\space` or `#\x41`. Any single
+/// character may follow the `#\`, even a delimiter like `(`.
+fn character(input: &str) -> IResult<&str, Token> {
+    let (after, _) = tag("#\\")(input)?;
+    let first = match after.chars().next() {
+        Some(first) => first,
+        None => return Err(SyntaxError::failure(input, "missing character after `#\\`")),
+    };
+    let (rest, name) = if DELIMITERS.contains(first) {
+        (&after[first.len_utf8()..], &after[..first.len_utf8()])
+    } else {
+        is_not(DELIMITERS)(after)?
+    };
+    let mut chars = name.chars();
+    let value = match (chars.next(), chars.next()) {
+        (Some(value), None) => value,
+        _ => match named_character(name) {
+            Some(value) => value,
+            None => {
+                return Err(SyntaxError::failure(
+                    input,
+                    format!("unknown character `#\\{}`", name),
+                ))
+            }
+        },
+    };
+    Ok((rest, Token::Character { value }))
+}
+
+/// Looks up a character name such as `space`, or a hex scalar value such as
+/// `x41`.
+fn named_character(name: &str) -> Option<char> {
+    if let Some(&(_, c)) = CHARACTER_NAMES.iter().find(|(n, _)| *n == name) {
+        return Some(c);
+    }
+    scalar_value(name.strip_prefix(['x', 'X'])?)
+}
+
 fn nil(input: &str) -> IResult<&str, Token> {
-    value(Token::Nil, tag("nil"))(input)
+    value(Token::Nil, verify(is_not(DELIMITERS), |atom: &str| atom == "nil"))(input)
 }
 
 fn dot(input: &str) -> IResult<&str, Token> {
-    value(Token::Dot, char('.'))(input)
+    value(Token::Dot, verify(is_not(DELIMITERS), |atom: &str| atom == "."))(input)
 }
 
 fn list_start(input: &str) -> IResult<&str, Token> {
@@ -81,115 +303,350 @@ fn list_end(input: &str) -> IResult<&str, Token> {
     value(Token::ListEnd, char(')'))(input)
 }
 
-fn rational(input: &str) -> IResult<&str, Token> {
-    map_res(tuple((integer, char('/'), integer)), |(n, _, d)| {
-        if let (Token::Integer { value: n }, Token::Integer { value: d }) = (n, d) {
-            Ok(Token::Rational {
-                numerator: n,
-                denominator: d,
+/// Characters that end an atom.
+pub(super) const DELIMITERS: &str = " \t\n\r()\";";
+
+/// Parses a number in any of the notations of R7RS section 7.1.1: an
+/// optional radix (`#x`, `#b`, `#o`, `#d`) and exactness (`#e`, `#i`) prefix,
+/// then a signed integer, rational or, in base 10, decimal.
+///
+/// The whole atom must be a number, so that `1+` and `-` are still read as
+/// symbols. An atom with a prefix can only be a number, though, so `#x` or
+/// `#e#i1` is an error.
+fn number(input: &str) -> IResult<&str, Token> {
+    let (rest, atom) = is_not(DELIMITERS)(input)?;
+    match all_consuming(number_literal(10))(atom) {
+        Ok((_, number)) => Ok((rest, number.into())),
+        Err(Err::Failure(SyntaxError {
+            message: Some(message),
+            ..
+        })) => Err(SyntaxError::failure(
+            input,
+            format!("invalid number `{}`: {}", atom, message),
+        )),
+        Err(_) if number_prefix(atom, 10).is_ok_and(|(after, _)| after.len() < atom.len()) => {
+            Err(SyntaxError::failure(input, format!("invalid number `{}`", atom)))
+        }
+        Err(_) => Err(Err::Error(SyntaxError::from_error_kind(
+            input,
+            ErrorKind::Digit,
+        ))),
+    }
+}
+
+/// Parses all of `text` as a number, as `string->number` does. Digits are in
+/// `radix` unless the number has a radix prefix.
+pub fn parse_number(text: &str, radix: u32) -> Option<Number> {
+    all_consuming(number_literal(radix))(text)
+        .ok()
+        .map(|(_, number)| number)
+}
+
+fn number_literal(default_radix: u32) -> impl FnMut(&str) -> IResult<&str, Number> {
+    move |input| {
+        let (input, (radix, exact)) = number_prefix(input, default_radix)?;
+        let (input, sign) = opt(one_of("+-"))(input)?;
+        let negative = sign == Some('-');
+        if sign.is_some() && exact != Some(true) {
+            if let Ok((rest, value)) = infinity_or_nan(input) {
+                return Ok((rest, Number::Float(if negative { -value } else { value })));
+            }
+        }
+        match exact {
+            Some(true) if radix == 10 => alt((
+                exact_decimal(negative),
+                unsigned_real(radix, negative),
+            ))(input),
+            Some(false) => map(unsigned_real(radix, negative), |number| {
+                Number::Float(number.to_f64())
+            })(input),
+            _ => unsigned_real(radix, negative)(input),
+        }
+    }
+}
+
+/// Parses the unsigned part of `+inf.0`, `-inf.0`, `+nan.0` or `-nan.0`.
+fn infinity_or_nan(input: &str) -> IResult<&str, f64> {
+    alt((
+        value(f64::INFINITY, tag_no_case("inf.0")),
+        value(f64::NAN, tag_no_case("nan.0")),
+    ))(input)
+}
+
+/// Parses the radix and exactness prefixes, in either order. The exactness
+/// is `None` if it is left to the notation of the number.
+fn number_prefix(input: &str, default_radix: u32) -> IResult<&str, (u32, Option<bool>)> {
+    let radix = || {
+        alt((
+            value(16, tag_no_case("#x")),
+            value(2, tag_no_case("#b")),
+            value(8, tag_no_case("#o")),
+            value(10, tag_no_case("#d")),
+        ))
+    };
+    let exactness = || alt((value(true, tag_no_case("#e")), value(false, tag_no_case("#i"))));
+    alt((
+        map(pair(radix(), opt(exactness())), |(radix, exact)| (radix, exact)),
+        map(pair(exactness(), opt(radix())), |(exact, radix)| {
+            (radix.unwrap_or(default_radix), Some(exact))
+        }),
+        success((default_radix, None)),
+    ))(input)
+}
+
+fn unsigned_real(radix: u32, negative: bool) -> impl FnMut(&str) -> IResult<&str, Number> {
+    move |input| {
+        let digits = || {
+            map_opt(take_while1(move |c: char| c.is_digit(radix)), move |digits: &str| {
+                BigInt::parse_bytes(digits.as_bytes(), radix)
             })
+        };
+        let sign = move |value: BigInt| if negative { -value } else { value };
+        let rational = move |input| {
+            let (rest, (numerator, denominator)) =
+                separated_pair(digits(), char('/'), digits())(input)?;
+            if denominator.is_zero() {
+                return Err(SyntaxError::failure(input, "zero denominator"));
+            }
+            Ok((rest, BigRational::new(sign(numerator), denominator).into()))
+        };
+        let float = map_opt(decimal, move |text| {
+            if radix != 10 {
+                return None;
+            }
+            let value: f64 = text.parse().ok()?;
+            Some(Number::Float(if negative { -value } else { value }))
+        });
+        let integer = map(digits(), move |value| sign(value).into());
+        alt((rational, float, integer))(input)
+    }
+}
+
+/// Recognizes an unsigned decimal with a point, an exponent or both, such as
+/// `1.5`, `.5`, `1.` or `1e10`.
+fn decimal(input: &str) -> IResult<&str, &str> {
+    let exponent = || tuple((one_of("eE"), opt(one_of("+-")), digit1));
+    recognize(alt((
+        tuple((digit1, char('.'), digit0, opt(exponent()))).map(|_| ()),
+        tuple((char('.'), digit1, opt(exponent()))).map(|_| ()),
+        tuple((digit1, exponent())).map(|_| ()),
+    )))(input)
+}
+
+/// The largest power of ten an exact decimal may be scaled by. Exact numbers
+/// are stored in full, so `#e1e999999999` would take forever to build.
+const MAX_EXACT_SCALE: u32 = 10_000;
+
+/// Parses a decimal as an exact number, so that `#e1.1` is `11/10` rather
+/// than the nearest binary fraction.
+fn exact_decimal(negative: bool) -> impl FnMut(&str) -> IResult<&str, Number> {
+    move |input| {
+        let (rest, text) = decimal(input)?;
+        let (mantissa, exponent) = match text.find(['e', 'E']) {
+            Some(at) => (&text[..at], text[at + 1..].parse::<i64>().unwrap_or(i64::MAX)),
+            None => (text, 0),
+        };
+        let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let scale = exponent.saturating_sub(fraction.len() as i64);
+        if scale.unsigned_abs() > MAX_EXACT_SCALE as u64 {
+            return Err(SyntaxError::failure(input, "exponent too large for an exact number"));
+        }
+        let mut numerator: BigInt = format!("{}{}", whole, fraction).parse().unwrap();
+        if negative {
+            numerator = -numerator;
+        }
+        let power = BigInt::from(10).pow(scale.unsigned_abs() as u32);
+        if scale >= 0 {
+            Ok((rest, (numerator * power).into()))
         } else {
-            Err(Err::Error((input, ErrorKind::Fail)))
+            Ok((rest, BigRational::new(numerator, power).into()))
         }
-    })(input)
+    }
 }
 
-fn float(input: &str) -> IResult<&str, Token> {
-    map_res(
-        tuple((
-            digit1,
-            tag("."),
-            digit1,
-            opt(tuple((
-                alt((tag("e"), tag("E"))),
-                opt(alt((tag("+"), tag("-")))),
-                digit1,
-            ))),
-        )),
-        |(a, _, b, c)| {
-            let mut s = String::from(a);
-            s.push('.');
-            s.push_str(b);
-            if let Some((_, sign, exp)) = c {
-                s.push('e');
-                if let Some(sign) = sign {
-                    s.push_str(sign);
+/// Parses a string. Line breaks and tabs may appear literally, and escapes
+/// are those of R7RS section 6.7, plus `\0` and `\u{...}`.
+fn string(input: &str) -> IResult<&str, Token> {
+    let (mut rest, _) = char('"')(input)?;
+    let mut value = String::new();
+    loop {
+        let mut chars = rest.chars();
+        match chars.next() {
+            // Unterminated; `diagnose` explains where the string started.
+            None => return Err(Err::Error(SyntaxError::from_error_kind(input, ErrorKind::Char))),
+            Some('"') => return Ok((chars.as_str(), Token::String { value })),
+            Some('\\') => match string_escape(rest) {
+                Ok((after, escaped)) => {
+                    value.extend(escaped);
+                    rest = after;
                 }
-                s.push_str(exp);
+                Err(Err::Error(_)) => {
+                    return Err(Err::Error(SyntaxError::from_error_kind(input, ErrorKind::Char)))
+                }
+                Err(err) => return Err(err),
+            },
+            Some(c) => {
+                value.push(c);
+                rest = chars.as_str();
             }
-            s.parse().map(|f| Token::Float { value: f })
-        },
-    )(input)
+        }
+    }
 }
 
-fn integer(input: &str) -> IResult<&str, Token> {
-    map_res(digit1, |input: &str| {
-        input.parse().map(|i| Token::Integer { value: i })
-    })(input)
+/// Parses an escape starting at a `\\` in a string, returning the character
+/// it stands for, or `None` for a line continuation. Fails softly only if the
+/// input ends inside the escape.
+fn string_escape(input: &str) -> IResult<&str, Option<char>> {
+    let incomplete = || Err(Err::Error(SyntaxError::from_error_kind(input, ErrorKind::Escaped)));
+    let invalid = |rest: &str, reason: &str| {
+        let text = &input[..input.len() - rest.len()];
+        SyntaxError::failure(input, format!("invalid escape `{}` in string{}", text, reason))
+    };
+    let mut chars = input[1..].chars();
+    let escaped = match chars.next() {
+        None => return incomplete(),
+        Some('\\') => '\\',
+        Some('"') => '"',
+        Some('\'') => '\'',
+        Some('n') => '\n',
+        Some('r') => '\r',
+        Some('t') => '\t',
+        Some('a') => '\u{7}',
+        Some('b') => '\u{8}',
+        Some('0') => '\0',
+        Some('x') => {
+            let digits = chars.as_str();
+            let rest = digits.trim_start_matches(|c: char| c.is_ascii_hexdigit());
+            let hex = &digits[..digits.len() - rest.len()];
+            let rest = match rest.strip_prefix(';') {
+                Some(rest) if !hex.is_empty() => rest,
+                _ if rest.is_empty() => return incomplete(),
+                _ => {
+                    let next = rest.chars().next().map_or(0, char::len_utf8);
+                    return Err(invalid(&rest[next..], ": expected hex digits and `;`"));
+                }
+            };
+            return match scalar_value(hex) {
+                Some(c) => Ok((rest, Some(c))),
+                None => Err(invalid(rest, ": not a Unicode scalar value")),
+            };
+        }
+        Some('u') => {
+            let braced = chars.as_str();
+            let digits = match braced.strip_prefix('{') {
+                Some(digits) => digits,
+                None if braced.is_empty() => return incomplete(),
+                None => return Err(invalid(braced, ": expected `{`, as in `\\u{3bb}`")),
+            };
+            let rest = digits.trim_start_matches(|c: char| c.is_ascii_hexdigit());
+            let hex = &digits[..digits.len() - rest.len()];
+            let rest = match rest.strip_prefix('}') {
+                Some(rest) if (1..=6).contains(&hex.len()) => rest,
+                _ if rest.is_empty() => return incomplete(),
+                _ => {
+                    let next = rest.chars().next().map_or(0, char::len_utf8);
+                    return Err(invalid(&rest[next..], ": expected 1 to 6 hex digits and `}`"));
+                }
+            };
+            return match scalar_value(hex) {
+                Some(c) => Ok((rest, Some(c))),
+                None => Err(invalid(rest, ": not a Unicode scalar value")),
+            };
+        }
+        Some(' ' | '\t' | '\n' | '\r') => {
+            let rest = input[1..].trim_start_matches([' ', '\t']);
+            let rest = match rest.strip_prefix("\r\n").or_else(|| rest.strip_prefix('\n')) {
+                Some(rest) => rest,
+                None if rest.is_empty() => return incomplete(),
+                None => {
+                    let reason = ": only spaces may follow `\\` at the end of a line";
+                    return Err(invalid(rest, reason));
+                }
+            };
+            return Ok((rest.trim_start_matches([' ', '\t']), None));
+        }
+        Some(c) => {
+            return Err(SyntaxError::failure(
+                input,
+                format!("invalid escape `\\{}` in string", c.escape_debug()),
+            ))
+        }
+    };
+    Ok((chars.as_str(), Some(escaped)))
 }
 
-fn string(input: &str) -> IResult<&str, Token> {
-    map(
-        delimited(
-            char('"'),
-            opt(escaped_transform(
-                none_of("\\\"\n\r\t"),
-                '\\',
-                alt((
-                    value('\\', char('\\')),
-                    value('"', char('"')),
-                    value('\'', char('\'')),
-                    value('\n', char('n')),
-                    value('\r', char('r')),
-                    value('\t', char('t')),
-                    // TODO: add unicode and other character escapes
-                )),
-            )),
-            char('"'),
-        ),
-        |input| Token::String {
-            value: input.unwrap_or_default().to_string(),
-        },
-    )(input)
+/// Converts the hex digits of an escape into a character.
+fn scalar_value(hex: &str) -> Option<char> {
+    char::from_u32(u32::from_str_radix(hex, 16).ok()?)
 }
 
-fn sharp_quote(input: &str) -> IResult<&str, Token> {
+/// Parses a datum preceded by `prefix`, such as `'x`, into a list such as
+/// `(quote x)`. Only the datum has an extent of its own.
+fn abbreviation<'a>(
+    prefix: &'static str,
+    name: Symbol,
+) -> impl FnMut(&'a str) -> IResult<&'a str, (Token, Vec<Extent>)> {
     map(
-        preceded(
-            tag("#'"),
-            parse_token,
-        ),
-        |input| Token::Cons {
-            head: Box::new(Token::Symbol { value: "function".to_string() }),
-            tail: Box::new(Token::Cons {
-                head: Box::new(input),
-                tail: Box::new(Token::EmptyList),
-            }),
+        preceded(pair(tag(prefix), atmosphere), datum),
+        move |(input, extent)| {
+            let list = Token::Cons {
+                head: Box::new(Token::Symbol { value: name }),
+                tail: Box::new(Token::Cons {
+                    head: Box::new(input),
+                    tail: Box::new(Token::EmptyList),
+                }),
+            };
+            (list, vec![extent])
         },
-    )(input)
+    )
 }
 
-fn quote(input: &str) -> IResult<&str, Token> {
-    map(
-        preceded(
-            char('\''),
-            parse_token,
-        ),
-        |input| Token::Cons {
-            head: Box::new(Token::Symbol { value: "quote".to_string() }),
-            tail: Box::new(Token::Cons {
-                head: Box::new(input),
-                tail: Box::new(Token::EmptyList),
-            }),
-        },
-    )(input)
+fn sharp_quote(input: &str) -> IResult<&str, (Token, Vec<Extent>)> {
+    abbreviation("#'", symbol::FUNCTION)(input)
+}
+
+fn quote(input: &str) -> IResult<&str, (Token, Vec<Extent>)> {
+    abbreviation("'", symbol::QUOTE)(input)
+}
+
+fn quasiquote(input: &str) -> IResult<&str, (Token, Vec<Extent>)> {
+    abbreviation("`", symbol::QUASIQUOTE)(input)
+}
+
+/// Parses both `,x` and `,@x`.
+fn unquote(input: &str) -> IResult<&str, (Token, Vec<Extent>)> {
+    alt((
+        abbreviation(",@", symbol::UNQUOTE_SPLICING),
+        abbreviation(",", symbol::UNQUOTE),
+    ))(input)
+}
+
+/// Rejects the datum labels `#0=` and `#0#`, which the printer writes for
+/// cycles. Data read here are trees, so they cannot be labelled.
+fn datum_label(input: &str) -> IResult<&str, Token> {
+    let (_, label) = recognize(tuple((char('#'), digit1, one_of("=#"))))(input)?;
+    Err(SyntaxError::failure(
+        input,
+        format!("datum labels such as `{}` are not supported", label),
+    ))
 }
 
+/// Parses any atom that is not something else. A lone `.` and atoms starting
+/// with a quote, an unquote, a comment, a character or a vector are left for
+/// the callers to reject.
 fn symbol(input: &str) -> IResult<&str, Token> {
     map(
-        pair(none_of(" .\t\n\r()"), opt(is_not(" \t\n\r()"))),
-        |(a, b)| Token::Symbol {
-            value: format!("{}{}", a, b.unwrap_or_default()),
+        preceded(
+            not(alt((tag("#;"), tag("#\\"), tag("#(")))),
+            verify(is_not(DELIMITERS), |atom: &str| {
+                atom != "."
+                    && !atom.starts_with(['\'', '`', ','])
+                    && !atom.starts_with("#'")
+                    && !atom.starts_with("#|")
+            }),
+        ),
+        |atom| Token::Symbol {
+            value: Symbol::intern(atom),
         },
     )
     .parse(input)
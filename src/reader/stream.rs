@@ -0,0 +1,330 @@
+//! Reading one datum at a time from a stream.
+//!
+//! [`Reader`] reads its input a line at a time, and only as far as it needs
+//! to finish the next datum. Lines are dropped once every form on them has
+//! been read, so the input never has to fit in memory at once.
+//!
+//! The parser starts over from the beginning of a datum each time it runs,
+//! so running it after every line would make reading a long datum
+//! quadratic. Instead a [`Scanner`] follows the lines as they arrive,
+//! tracking just enough lexical state (nesting, strings and comments) to
+//! tell where a datum might end, and the parser only runs once one can.
+
+use std::io::BufRead;
+
+use crate::error::{Error, Result};
+use crate::token::Spanned;
+
+use super::parser::{parse_next_token, DELIMITERS};
+use super::{explain, LineIndex};
+
+/// An iterator over the tokens in a stream, including comments, each with
+/// its span.
+///
+/// Reading stops at the first error. If the input ends inside an open list,
+/// string or comment, the error is [`Error::UnexpectedEof`] rather than
+/// [`Error::ReadError`].
+///
+/// Input can also be pushed into the reader a line at a time with
+/// [`Reader::push_line`] and read back with [`Reader::read_buffered`], as
+/// an interactive session does.
+pub struct Reader<R> {
+    input: R,
+    file: Option<String>,
+    /// The lines read but not yet fully parsed.
+    buffer: String,
+    /// How many bytes of `buffer` have been parsed.
+    parsed: usize,
+    /// The byte offset and line number of the start of `buffer`.
+    offset: usize,
+    line: usize,
+    scanner: Scanner,
+    done: bool,
+}
+
+impl<R: BufRead> Reader<R> {
+    /// Creates a reader over `input`. `file` is only used to label the spans
+    /// and diagnostics.
+    pub fn new(input: R, file: Option<&str>) -> Self {
+        Reader {
+            input,
+            file: file.map(str::to_string),
+            buffer: String::new(),
+            parsed: 0,
+            offset: 0,
+            line: 1,
+            scanner: Scanner::default(),
+            done: false,
+        }
+    }
+
+    fn read_next(&mut self) -> Result<Option<Spanned>> {
+        loop {
+            if let Some(spanned) = self.read_buffered()? {
+                return Ok(Some(spanned));
+            }
+            let start = self.buffer.len();
+            if self.input.read_line(&mut self.buffer)? == 0 {
+                return self.parse_next(true);
+            }
+            self.scanner.scan(&self.buffer[start..], start);
+        }
+    }
+}
+
+impl<R> Reader<R> {
+    /// Adds a line of input, as if it had been read from the stream.
+    pub fn push_line(&mut self, line: &str) {
+        let start = self.buffer.len();
+        self.buffer.push_str(line);
+        self.scanner.scan(line, start);
+    }
+
+    /// Reads the next token if the input so far holds all of it, without
+    /// reading any more.
+    pub fn read_buffered(&mut self) -> Result<Option<Spanned>> {
+        self.discard_parsed_lines();
+        if self.scanner.complete <= self.parsed {
+            return Ok(None);
+        }
+        let spanned = self.parse_next(false)?;
+        if spanned.is_none() {
+            // The next token goes on past every end the scanner has seen.
+            self.scanner.complete = self.parsed;
+        }
+        Ok(spanned)
+    }
+
+    /// Whether some of the input is still unread, such as the start of an
+    /// unfinished list.
+    pub fn is_pending(&self) -> bool {
+        self.scanner.significant > self.parsed
+    }
+
+    /// Drops the input not yet read, after an error for instance.
+    pub fn clear(&mut self) {
+        self.parsed = self.buffer.len();
+        self.scanner = Scanner::default();
+        self.discard_parsed_lines();
+    }
+
+    /// Parses the next token in the buffer. Unless the input has ended, a
+    /// token that runs past the end of the buffer gives `None`.
+    fn parse_next(&mut self, eof: bool) -> Result<Option<Spanned>> {
+        let lines = LineIndex::starting_at(&self.buffer, self.offset, self.line);
+        let file = self.file.as_deref();
+        match parse_next_token(&self.buffer[self.parsed..]) {
+            Ok((_, None)) => Ok(None),
+            Ok((rest, Some((token, extent)))) => {
+                self.parsed = self.buffer.len() - rest.len();
+                let span = lines.locate(&self.buffer, file, &extent);
+                Ok(Some(Spanned { token, span }))
+            }
+            Err(err) => {
+                let problem = explain(&self.buffer, err, &lines);
+                if !problem.incomplete {
+                    Err(lines.error(&self.buffer, file, problem.offset, problem.message))
+                } else if eof {
+                    Err(Error::UnexpectedEof(Box::new(lines.diagnostic(
+                        &self.buffer,
+                        file,
+                        problem.offset,
+                        problem.message,
+                    ))))
+                } else {
+                    Ok(None)
+                }
+            }
+        }
+    }
+
+    /// Drops the lines before the next unparsed byte.
+    fn discard_parsed_lines(&mut self) {
+        if let Some(end) = self.buffer[..self.parsed].rfind('\n') {
+            let end = end + 1;
+            self.line += self.buffer[..end].matches('\n').count();
+            self.offset += end;
+            self.parsed -= end;
+            self.buffer.drain(..end);
+            self.scanner.shift(end);
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Reader<R> {
+    type Item = Result<Spanned>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.read_next();
+        // After an error the rest of the input cannot be trusted.
+        self.done = !matches!(result, Ok(Some(_)));
+        result.transpose()
+    }
+}
+
+/// Follows the input a character at a time, recording where tokens end at
+/// the top level. Its offsets are into the reader's buffer.
+///
+/// It only needs to be right about where a token cannot end yet: an end it
+/// records too early just makes the parser run and find the token
+/// incomplete.
+#[derive(Debug, Default)]
+struct Scanner {
+    state: State,
+    /// How deeply nested in lists and vectors the scanned input ends.
+    depth: usize,
+    /// The end of the last token finished at the top level.
+    complete: usize,
+    /// The end of the last character that is not whitespace between tokens.
+    significant: usize,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+enum State {
+    /// Between tokens, or after a prefix such as `'`.
+    #[default]
+    Between,
+    Atom,
+    /// After a `#` that starts a token.
+    Sharp,
+    /// After a `,`, which may be the start of `,@`.
+    Comma,
+    /// After `#\`, where even a delimiter is part of the character.
+    Character,
+    String {
+        escaped: bool,
+    },
+    LineComment,
+    /// Inside `depth` nested block comments, just after `last`.
+    BlockComment {
+        depth: usize,
+        last: char,
+    },
+}
+
+impl Scanner {
+    /// Scans `text`, which was appended to the buffer at byte `start`.
+    fn scan(&mut self, text: &str, start: usize) {
+        for (i, c) in text.char_indices() {
+            let end = start + i + c.len_utf8();
+            self.step(c, start + i, end);
+            if !(is_whitespace(c) && self.state == State::Between) {
+                self.significant = end;
+            }
+        }
+    }
+
+    /// Follows the character `c`, which spans the bytes `offset..end`.
+    fn step(&mut self, c: char, offset: usize, end: usize) {
+        match self.state {
+            State::Between => self.start(c, end),
+            State::Atom if DELIMITERS.contains(c) => {
+                self.finish(offset);
+                self.state = State::Between;
+                self.start(c, end);
+            }
+            State::Atom => {}
+            State::Sharp => match c {
+                '(' => {
+                    self.depth += 1;
+                    self.state = State::Between;
+                }
+                '|' => {
+                    self.state = State::BlockComment {
+                        depth: 1,
+                        last: ' ',
+                    }
+                }
+                '\\' => self.state = State::Character,
+                '\'' | ';' => self.state = State::Between,
+                _ => {
+                    self.state = State::Atom;
+                    self.step(c, offset, end);
+                }
+            },
+            State::Comma => {
+                self.state = State::Between;
+                if c != '@' {
+                    self.start(c, end);
+                }
+            }
+            State::Character => self.state = State::Atom,
+            State::String { escaped } => match c {
+                '"' if !escaped => {
+                    self.state = State::Between;
+                    self.finish(end);
+                }
+                _ => {
+                    self.state = State::String {
+                        escaped: c == '\\' && !escaped,
+                    }
+                }
+            },
+            State::LineComment => {
+                if c == '\n' || c == '\r' {
+                    self.state = State::Between;
+                    self.finish(offset);
+                }
+            }
+            State::BlockComment { depth, last } => {
+                self.state = match (last, c) {
+                    ('|', '#') if depth == 1 => {
+                        self.finish(end);
+                        State::Between
+                    }
+                    // A `#` or `|` used up here cannot start another pair.
+                    ('|', '#') => State::BlockComment {
+                        depth: depth - 1,
+                        last: ' ',
+                    },
+                    ('#', '|') => State::BlockComment {
+                        depth: depth + 1,
+                        last: ' ',
+                    },
+                    _ => State::BlockComment { depth, last: c },
+                }
+            }
+        }
+    }
+
+    /// Follows `c`, which starts a token or is whitespace between tokens.
+    fn start(&mut self, c: char, end: usize) {
+        match c {
+            '(' => self.depth += 1,
+            // An unmatched `)` is a token of its own, for the parser to
+            // reject.
+            ')' => {
+                self.depth = self.depth.saturating_sub(1);
+                self.finish(end);
+            }
+            '"' => self.state = State::String { escaped: false },
+            ';' => self.state = State::LineComment,
+            ',' => self.state = State::Comma,
+            '#' => self.state = State::Sharp,
+            '\'' | '`' => {}
+            c if is_whitespace(c) => {}
+            _ => self.state = State::Atom,
+        }
+    }
+
+    /// Records that a token ends at `end`, if it is at the top level.
+    fn finish(&mut self, end: usize) {
+        if self.depth == 0 {
+            self.complete = end;
+        }
+    }
+
+    /// Adjusts the offsets after the first `count` bytes of the buffer are
+    /// dropped.
+    fn shift(&mut self, count: usize) {
+        self.complete = self.complete.saturating_sub(count);
+        self.significant = self.significant.saturating_sub(count);
+    }
+}
+
+fn is_whitespace(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n' | '\r')
+}
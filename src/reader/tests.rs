@@ -1,8 +1,13 @@
+#![allow(clippy::approx_constant)]
+
 use std::str::FromStr;
 
+use num_bigint::BigInt;
 use pretty_assertions::assert_eq;
 
-use crate::reader::{read_lisp, Token};
+use crate::error::{Diagnostic, Error, Result};
+use crate::reader::{read_lisp, read_lisp_spanned, Reader, Token};
+use crate::token::Span;
 
 use Token::*;
 
@@ -20,10 +25,29 @@ fn from_f64() {
 fn from_int_pair() {
     assert_eq!(
         Rational {
-            numerator: 13,
-            denominator: 74
+            numerator: BigInt::from(13),
+            denominator: BigInt::from(74)
+        },
+        Token::try_from((13, 74)).unwrap()
+    );
+}
+
+#[test]
+fn from_int_pair_zero_denominator() {
+    assert!(matches!(
+        Token::try_from((1, 0)),
+        Err(Error::DivisionByZero(_))
+    ));
+}
+
+#[test]
+fn from_int_pair_reduced() {
+    assert_eq!(
+        Rational {
+            numerator: BigInt::from(-1),
+            denominator: BigInt::from(2)
         },
-        (13, 74).into()
+        Token::try_from((2, -4)).unwrap()
     );
 }
 
@@ -96,7 +120,7 @@ test_from_str_input!(
     from_str_symbol,
     "foobar",
     Symbol {
-        value: "foobar".to_string()
+        value: "foobar".into()
     }
 );
 test_from_str_input!(from_str_float, "3.14159", Float { value: 3.14159 });
@@ -104,8 +128,8 @@ test_from_str_input!(
     from_str_rational,
     "2/3",
     Rational {
-        numerator: 2,
-        denominator: 3
+        numerator: BigInt::from(2),
+        denominator: BigInt::from(3)
     }
 );
 test_from_str_input!(
@@ -129,7 +153,154 @@ test_from_str_input!(
         value: "Hello, \"World!\"".to_string()
     }
 );
+test_from_str_input!(
+    from_str_string_hex_escapes,
+    r#""\x41;\x3bb;\a\0""#,
+    String {
+        value: "A\u{3bb}\u{7}\0".to_string()
+    }
+);
+test_from_str_input!(
+    from_str_string_unicode_escape,
+    r#""smile \u{1F600}""#,
+    String {
+        value: "smile \u{1F600}".to_string()
+    }
+);
+test_from_str_input!(
+    from_str_string_line_continuation,
+    "\"one \\  \n    two\"",
+    String {
+        value: "one two".to_string()
+    }
+);
+test_from_str_input!(
+    from_str_string_multiline,
+    "\"one\n\ttwo\"",
+    String {
+        value: "one\n\ttwo".to_string()
+    }
+);
 test_from_str_input!(from_str_nil, "nil", Nil);
+test_from_str_input!(from_str_negative_integer, "-5", Integer { value: -5 });
+test_from_str_input!(from_str_positive_integer, "+3", Integer { value: 3 });
+test_from_str_input!(from_str_negative_float, "-2.5", Float { value: -2.5 });
+test_from_str_input!(from_str_float_no_leading_digit, ".5", Float { value: 0.5 });
+test_from_str_input!(from_str_float_no_fraction, "1.", Float { value: 1.0 });
+test_from_str_input!(from_str_float_exponent, "1e10", Float { value: 1e10 });
+test_from_str_input!(
+    from_str_float_signed_exponent,
+    "-.5E-3",
+    Float { value: -0.0005 }
+);
+test_from_str_input!(
+    from_str_negative_rational,
+    "-1/2",
+    Rational {
+        numerator: BigInt::from(-1),
+        denominator: BigInt::from(2)
+    }
+);
+test_from_str_input!(
+    from_str_big_integer,
+    "-123456789012345678901234567890",
+    BigInteger {
+        value: "-123456789012345678901234567890".parse().unwrap()
+    }
+);
+test_from_str_input!(
+    from_str_rational_reduced,
+    "-6/4",
+    Rational {
+        numerator: BigInt::from(-3),
+        denominator: BigInt::from(2)
+    }
+);
+test_from_str_input!(from_str_rational_integral, "4/2", Integer { value: 2 });
+test_from_str_input!(from_str_hex, "#x1F", Integer { value: 31 });
+test_from_str_input!(from_str_hex_upper, "#XfF", Integer { value: 255 });
+test_from_str_input!(from_str_binary, "#b-101", Integer { value: -5 });
+test_from_str_input!(from_str_octal, "#o17", Integer { value: 15 });
+test_from_str_input!(from_str_decimal, "#d10", Integer { value: 10 });
+test_from_str_input!(
+    from_str_hex_rational,
+    "#x-a/10",
+    Rational {
+        numerator: BigInt::from(-5),
+        denominator: BigInt::from(8)
+    }
+);
+test_from_str_input!(
+    from_str_exact_float,
+    "#e1.1",
+    Rational {
+        numerator: BigInt::from(11),
+        denominator: BigInt::from(10)
+    }
+);
+test_from_str_input!(from_str_exact_exponent, "#e1.5e3", Integer { value: 1500 });
+test_from_str_input!(from_str_inexact_integer, "#i3", Float { value: 3.0 });
+test_from_str_input!(from_str_inexact_rational, "#i-1/4", Float { value: -0.25 });
+test_from_str_input!(from_str_exact_hex, "#e#x10", Integer { value: 16 });
+test_from_str_input!(from_str_hex_inexact, "#x#i10", Float { value: 16.0 });
+test_from_str_input!(from_str_minus, "-", Symbol { value: "-".into() });
+test_from_str_input!(from_str_one_plus, "1+", Symbol { value: "1+".into() });
+test_from_str_input!(
+    from_str_negative_symbol,
+    "-x",
+    Symbol { value: "-x".into() }
+);
+test_from_str_input!(
+    from_str_positive_infinity,
+    "+inf.0",
+    Float {
+        value: f64::INFINITY
+    }
+);
+test_from_str_input!(
+    from_str_negative_infinity,
+    "-inf.0",
+    Float {
+        value: f64::NEG_INFINITY
+    }
+);
+test_from_str_input!(
+    from_str_unsigned_inf,
+    "inf.0",
+    Symbol {
+        value: "inf.0".into()
+    }
+);
+test_from_str_input!(from_str_true, "#t", Boolean { value: true });
+test_from_str_input!(from_str_true_long, "#true", Boolean { value: true });
+test_from_str_input!(from_str_false, "#f", Boolean { value: false });
+test_from_str_input!(from_str_false_long, "#false", Boolean { value: false });
+test_from_str_input!(from_str_character, "#\\a", Character { value: 'a' });
+test_from_str_input!(
+    from_str_character_space,
+    "#\\space",
+    Character { value: ' ' }
+);
+test_from_str_input!(
+    from_str_character_newline,
+    "#\\newline",
+    Character { value: '\n' }
+);
+test_from_str_input!(from_str_character_hex, "#\\x41", Character { value: 'A' });
+test_from_str_input!(from_str_character_x, "#\\x", Character { value: 'x' });
+test_from_str_input!(from_str_character_paren, "#\\(", Character { value: '(' });
+test_from_str_input!(
+    from_str_vector,
+    "#(1 #t (a))",
+    Vector {
+        items: vec![
+            Integer { value: 1 },
+            Boolean { value: true },
+            vec![Symbol { value: "a".into() }].into()
+        ]
+    }
+);
+test_from_str_input!(from_str_empty_vector, "#()", Vector { items: vec![] });
 // test_from_str_input!(from_str_list_start, "(", ListStart);
 // test_from_str_input!(from_str_list_end, ")", ListEnd);
 
@@ -177,7 +348,7 @@ test_parse_input!(
     parse_symbol_with_digits,
     "symbol-42",
     Symbol {
-        value: "symbol-42".to_string()
+        value: "symbol-42".into()
     }
 );
 test_parse_input!(
@@ -185,13 +356,13 @@ test_parse_input!(
     "(symbol-42 symbol-43 symbol-44)",
     vec![
         Token::Symbol {
-            value: "symbol-42".to_string()
+            value: "symbol-42".into()
         },
         Token::Symbol {
-            value: "symbol-43".to_string()
+            value: "symbol-43".into()
         },
         Token::Symbol {
-            value: "symbol-44".to_string()
+            value: "symbol-44".into()
         }
     ]
     .into()
@@ -200,14 +371,10 @@ test_parse_input!(
     parse_embedded_lists,
     "(+ 7 (- 10 3))",
     vec![
-        Symbol {
-            value: "+".to_string()
-        },
+        Symbol { value: "+".into() },
         7i64.into(),
         vec![
-            Symbol {
-                value: "-".to_string()
-            },
+            Symbol { value: "-".into() },
             Into::<Token>::into(10i64),
             3.into()
         ]
@@ -219,24 +386,42 @@ test_parse_input!(
 test_parse_input!(
     parse_operators,
     "+ - 42",
-    Symbol {
-        value: "+".to_string()
-    },
-    Symbol {
-        value: "-".to_string()
-    },
+    Symbol { value: "+".into() },
+    Symbol { value: "-".into() },
     Integer { value: 42 }
 );
 
+test_parse_input!(
+    parse_negative_numbers,
+    "(- -5 +3 -2.5 .5 -1/2)",
+    vec![
+        Symbol { value: "-".into() },
+        Into::<Token>::into(-5i64),
+        3.into(),
+        (-2.5).into(),
+        0.5.into(),
+        Token::try_from((-1, 2)).unwrap(),
+    ]
+    .into()
+);
+test_parse_input!(
+    parse_dotted_float,
+    "(1 . .5)",
+    Cons {
+        head: Box::new(Integer { value: 1 }),
+        tail: Box::new(Float { value: 0.5 })
+    }
+);
+
 test_parse_input!(
     parse_quoted_symbol,
     "'foobar",
     vec![
         Symbol {
-            value: "quote".to_string()
+            value: "quote".into()
         },
         Symbol {
-            value: "foobar".to_string()
+            value: "foobar".into()
         },
     ]
     .into()
@@ -247,45 +432,720 @@ test_parse_input!(
     "'(+ 1 3)",
     vec![
         Symbol {
-            value: "quote".to_string()
+            value: "quote".into()
         },
+        vec![Symbol { value: "+".into() }, 1.into(), 3.into(),].into(),
+    ]
+    .into()
+);
+
+test_parse_input!(
+    parse_quoted_function,
+    "#'foo-bar",
+    vec![
+        Symbol {
+            value: "function".into()
+        },
+        Symbol {
+            value: "foo-bar".into()
+        },
+    ]
+    .into()
+);
+
+test_parse_input!(
+    parse_quotes_in_list,
+    "(a 'b (f #'g))",
+    vec![
+        Symbol { value: "a".into() },
         vec![
             Symbol {
-                value: "+".to_string()
+                value: "quote".into()
             },
-            1.into(),
-            3.into(),
+            Symbol { value: "b".into() }
+        ]
+        .into(),
+        vec![
+            Symbol { value: "f".into() },
+            vec![
+                Symbol {
+                    value: "function".into()
+                },
+                Symbol { value: "g".into() }
+            ]
+            .into(),
         ]
         .into(),
     ]
     .into()
 );
-
 test_parse_input!(
-    parse_quoted_function,
-    "#'foo-bar",
+    parse_quotes_nested,
+    "(a (b '(c 'd) ''e))",
+    vec![
+        Symbol { value: "a".into() },
+        vec![
+            Symbol { value: "b".into() },
+            vec![
+                Symbol {
+                    value: "quote".into()
+                },
+                vec![
+                    Symbol { value: "c".into() },
+                    vec![
+                        Symbol {
+                            value: "quote".into()
+                        },
+                        Symbol { value: "d".into() }
+                    ]
+                    .into(),
+                ]
+                .into(),
+            ]
+            .into(),
+            vec![
+                Symbol {
+                    value: "quote".into()
+                },
+                vec![
+                    Symbol {
+                        value: "quote".into()
+                    },
+                    Symbol { value: "e".into() }
+                ]
+                .into(),
+            ]
+            .into(),
+        ]
+        .into(),
+    ]
+    .into()
+);
+test_parse_input!(
+    parse_quasiquote,
+    "`(a ,b ,@c)",
+    vec![
+        Symbol {
+            value: "quasiquote".into()
+        },
+        vec![
+            Symbol { value: "a".into() },
+            vec![
+                Symbol {
+                    value: "unquote".into()
+                },
+                Symbol { value: "b".into() }
+            ]
+            .into(),
+            vec![
+                Symbol {
+                    value: "unquote-splicing".into()
+                },
+                Symbol { value: "c".into() }
+            ]
+            .into(),
+        ]
+        .into(),
+    ]
+    .into()
+);
+test_parse_input!(
+    parse_unquote_dotted_tail,
+    "(a . ,b)",
+    Cons {
+        head: Box::new(Symbol { value: "a".into() }),
+        tail: Box::new(
+            vec![
+                Symbol {
+                    value: "unquote".into()
+                },
+                Symbol { value: "b".into() }
+            ]
+            .into()
+        )
+    }
+);
+test_parse_input!(
+    parse_comments_in_list,
+    "(define (f n) ; a function\n  ;; the body\n  (g n ; the argument\n     ) ; done\n)",
     vec![
         Symbol {
-            value: "function".to_string()
+            value: "define".into()
         },
+        vec![Symbol { value: "f".into() }, Symbol { value: "n".into() }].into(),
+        vec![Symbol { value: "g".into() }, Symbol { value: "n".into() }].into(),
+    ]
+    .into()
+);
+test_parse_input!(
+    parse_comment_around_dot,
+    "(a ; before\n . ; after\n b)",
+    Cons {
+        head: Box::new(Symbol { value: "a".into() }),
+        tail: Box::new(Symbol { value: "b".into() })
+    }
+);
+test_parse_input!(
+    parse_quote_before_comment,
+    "'; the quoted datum follows\nx",
+    vec![
         Symbol {
-            value: "foo-bar".to_string()
+            value: "quote".into()
         },
+        Symbol { value: "x".into() }
     ]
     .into()
 );
+test_parse_input!(
+    parse_adjacent_lists,
+    "(a)(b)",
+    vec![Symbol { value: "a".into() }].into(),
+    vec![Symbol { value: "b".into() }].into()
+);
+test_parse_input!(
+    parse_nil_prefix,
+    "(nil nilly)",
+    vec![
+        Nil,
+        Symbol {
+            value: "nilly".into()
+        }
+    ]
+    .into()
+);
+test_parse_input!(
+    parse_ellipsis,
+    "...",
+    Symbol {
+        value: "...".into()
+    }
+);
 
 test_parse_input!(
     parse_comments,
     "something ; commented\nsomething-else",
     Symbol {
-        value: "something".to_string()
+        value: "something".into()
     },
     Comment {
         depth: 1,
         comment: " commented".to_string(),
     },
     Symbol {
-        value: "something-else".to_string()
+        value: "something-else".into()
     }
 );
+
+test_parse_input!(
+    parse_block_comment,
+    "a #| one\n #| nested |# two |# b",
+    Symbol { value: "a".into() },
+    BlockComment {
+        comment: " one\n #| nested |# two ".to_string(),
+    },
+    Symbol { value: "b".into() }
+);
+test_parse_input!(
+    parse_datum_comment,
+    "#; (define x 1) x",
+    DatumComment {
+        datum: Box::new(
+            vec![
+                Symbol {
+                    value: "define".into()
+                },
+                Symbol { value: "x".into() },
+                Integer { value: 1 }
+            ]
+            .into()
+        )
+    },
+    Symbol { value: "x".into() }
+);
+test_parse_input!(
+    parse_comments_skipped_in_list,
+    "(a #| b |# #;(c d) e #;f)",
+    vec![Symbol { value: "a".into() }, Symbol { value: "e".into() }].into()
+);
+test_parse_input!(
+    parse_nested_datum_comments,
+    "(a #; #; b c d)",
+    vec![Symbol { value: "a".into() }, Symbol { value: "d".into() }].into()
+);
+
+test_parse_input!(
+    parse_characters_in_list,
+    "(#\\( #\\) #\\; #\\\" #\\ )",
+    vec![
+        Character { value: '(' },
+        Character { value: ')' },
+        Character { value: ';' },
+        Character { value: '"' },
+        Character { value: ' ' }
+    ]
+    .into()
+);
+test_parse_input!(
+    parse_vector_with_comments,
+    "#(a ; one\n #;b c)",
+    Vector {
+        items: vec![Symbol { value: "a".into() }, Symbol { value: "c".into() }]
+    }
+);
+
+/// Pushes `input` into a reader a line at a time, as the REPL does, and
+/// reads every token it completes. Returns whether input is left pending.
+fn read_pushed(input: &str) -> Result<(Vec<Token>, bool)> {
+    let mut reader = Reader::new(std::io::empty(), None);
+    let mut tokens = Vec::new();
+    for line in input.split_inclusive('\n') {
+        reader.push_line(line);
+        while let Some(spanned) = reader.read_buffered()? {
+            tokens.push(spanned.token);
+        }
+    }
+    Ok((tokens, reader.is_pending()))
+}
+
+macro_rules! test_read_pushed {
+    ($name:ident, $input:expr, $pending:expr, $($expected:expr),*) => {
+        #[test]
+        fn $name() {
+            let actual = read_pushed($input);
+            assert!(actual.is_ok(), "{:?}", actual);
+            assert_eq!(actual.unwrap(), (vec![$($expected),*], $pending));
+        }
+    };
+}
+
+test_read_pushed!(
+    read_pushed_complete,
+    "(+ 1 2)\n",
+    false,
+    vec![Symbol { value: "+".into() }, 1i64.into(), 2.into()].into()
+);
+test_read_pushed!(read_pushed_open_list, "(define (f x)\n", true,);
+test_read_pushed!(read_pushed_open_nested_list, "(a (b c) (d\n", true,);
+test_read_pushed!(read_pushed_open_string, "(f \"a (b\n", true,);
+test_read_pushed!(
+    read_pushed_paren_in_string,
+    "(f \"(\")",
+    false,
+    vec![Symbol { value: "f".into() }, "(".into()].into()
+);
+test_read_pushed!(read_pushed_open_block_comment, "#| a\n (b\n", true,);
+test_read_pushed!(
+    read_pushed_trailing_datum_comment,
+    "(a) #;\n",
+    true,
+    vec![Symbol { value: "a".into() }].into()
+);
+test_read_pushed!(read_pushed_open_hex_escape, "(f \"\\x4", true,);
+test_read_pushed!(
+    read_pushed_over_lines,
+    "(a\n #\\( \"b)\n\" ; c)\n #| d) |# e)\n",
+    false,
+    vec![
+        Symbol { value: "a".into() },
+        Character { value: '(' },
+        "b)\n".into(),
+        Symbol { value: "e".into() }
+    ]
+    .into()
+);
+test_read_pushed!(
+    read_pushed_nested_block_comment,
+    "#| a #| b |#\n c |# (d)\n",
+    false,
+    BlockComment {
+        comment: " a #| b |#\n c ".to_string()
+    },
+    vec![Symbol { value: "d".into() }].into()
+);
+
+#[test]
+fn read_pushed_unbalanced() {
+    assert!(read_pushed("(a))").is_err());
+}
+
+#[test]
+fn read_spans() {
+    let mut input = "(define x\n  42)\n\n  \"é\" foo".as_bytes();
+    let spans: Vec<Span> = read_lisp_spanned(&mut input, Some("test.lisp"))
+        .unwrap()
+        .into_iter()
+        .map(|spanned| spanned.span)
+        .collect();
+    assert_eq!(
+        spans,
+        vec![
+            Span {
+                file: Some("test.lisp".to_string()),
+                start: 0,
+                end: 15,
+                line: 1,
+                column: 1,
+                end_line: 2,
+                end_column: 6,
+                items: vec![
+                    Span {
+                        file: Some("test.lisp".to_string()),
+                        start: 1,
+                        end: 7,
+                        line: 1,
+                        column: 2,
+                        end_line: 1,
+                        end_column: 8,
+                        items: vec![],
+                    },
+                    Span {
+                        file: Some("test.lisp".to_string()),
+                        start: 8,
+                        end: 9,
+                        line: 1,
+                        column: 9,
+                        end_line: 1,
+                        end_column: 10,
+                        items: vec![],
+                    },
+                    Span {
+                        file: Some("test.lisp".to_string()),
+                        start: 12,
+                        end: 14,
+                        line: 2,
+                        column: 3,
+                        end_line: 2,
+                        end_column: 5,
+                        items: vec![],
+                    },
+                ],
+            },
+            Span {
+                file: Some("test.lisp".to_string()),
+                start: 19,
+                end: 23,
+                line: 4,
+                column: 3,
+                end_line: 4,
+                end_column: 6,
+                items: vec![],
+            },
+            Span {
+                file: Some("test.lisp".to_string()),
+                start: 24,
+                end: 27,
+                line: 4,
+                column: 7,
+                end_line: 4,
+                end_column: 10,
+                items: vec![],
+            },
+        ]
+    );
+}
+
+/// The byte ranges of the data inside a span, as a tree of `(start, end)`
+/// pairs written out as a string.
+fn item_ranges(span: &Span) -> std::string::String {
+    let items: Vec<_> = span.items.iter().map(item_ranges).collect();
+    if items.is_empty() {
+        format!("{}..{}", span.start, span.end)
+    } else {
+        format!("{}..{}[{}]", span.start, span.end, items.join(" "))
+    }
+}
+
+#[test]
+fn read_nested_spans() {
+    let mut input = "(a (b . c) #(d 'e) #;f #| g |# h)".as_bytes();
+    let spanned = read_lisp_spanned(&mut input, None).unwrap();
+    assert_eq!(
+        item_ranges(&spanned[0].span),
+        "0..33[1..2 3..10[4..5 8..9] 11..18[13..14 15..17[16..17]] 31..32]"
+    );
+}
+
+#[test]
+fn read_nested_span_positions() {
+    let mut input = "; é\n(a\n  (é b))".as_bytes();
+    let spanned = read_lisp_spanned(&mut input, None).unwrap();
+    let inner = &spanned[1].span.items[1];
+    assert_eq!((inner.line, inner.column, inner.end_column), (3, 3, 8));
+    let b = &inner.items[1];
+    assert_eq!((b.start, b.line, b.column), (14, 3, 6));
+}
+
+#[test]
+fn read_span_columns_on_a_long_line() {
+    let input = format!("a\n({}x)", "é ".repeat(200));
+    let spanned = read_lisp_spanned(&mut input.as_bytes(), None).unwrap();
+    let x = &spanned[1].span.items[200];
+    assert_eq!((x.start, x.line, x.column), (603, 2, 402));
+}
+
+#[test]
+fn read_datum_comment_span() {
+    let mut input = "#; (a b)".as_bytes();
+    let spanned = read_lisp_spanned(&mut input, None).unwrap();
+    assert_eq!(item_ranges(&spanned[0].span), "0..8[3..8[4..5 6..7]]");
+}
+
+fn read_error(input: &str) -> Diagnostic {
+    match read_lisp(&mut input.as_bytes()) {
+        Err(Error::ReadError(diagnostic)) => *diagnostic,
+        other => panic!("expected a read error, got {:?}", other),
+    }
+}
+
+#[test]
+fn read_error_unclosed_list() {
+    let diagnostic = read_error("(define x\n  (foo 1\n");
+    assert_eq!(diagnostic.message, "unclosed `(` opened at 2:3");
+    assert_eq!((diagnostic.line, diagnostic.column), (2, 3));
+    assert_eq!(diagnostic.source_line, "  (foo 1");
+}
+
+#[test]
+fn read_error_unterminated_string() {
+    let diagnostic = read_error("(a)\n\"abc");
+    assert_eq!(diagnostic.message, "unterminated string starting at 2:1");
+}
+
+#[test]
+fn read_error_invalid_escape() {
+    let diagnostic = read_error("(display \"a\\qb\")");
+    assert_eq!(diagnostic.message, "invalid escape `\\q` in string");
+    assert_eq!((diagnostic.line, diagnostic.column), (1, 12));
+}
+
+#[test]
+fn read_error_unmatched_close() {
+    let diagnostic = read_error("(a))");
+    assert_eq!(diagnostic.message, "unexpected `)` with no matching `(`");
+    assert_eq!((diagnostic.line, diagnostic.column), (1, 4));
+}
+
+#[test]
+fn read_error_display() {
+    let mut input = "(a\n\t(b c".as_bytes();
+    let err = read_lisp_spanned(&mut input, Some("test.lisp")).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "unclosed `(` opened at 2:2\n --> test.lisp:2:2\n  |\n2 | \t(b c\n  | \t^"
+    );
+}
+
+#[test]
+fn read_pushed_invalid_escape() {
+    match read_pushed("(f \"\\q\")") {
+        Err(Error::ReadError(diagnostic)) => {
+            assert_eq!(diagnostic.message, "invalid escape `\\q` in string")
+        }
+        other => panic!("expected a read error, got {:?}", other),
+    }
+}
+
+#[test]
+fn from_str_error() {
+    match "(a b".parse::<Token>() {
+        Err(Error::ReadError(diagnostic)) => {
+            assert_eq!(diagnostic.message, "unclosed `(` opened at 1:1")
+        }
+        other => panic!("expected a read error, got {:?}", other),
+    }
+}
+
+#[test]
+fn read_error_zero_denominator() {
+    let diagnostic = read_error("(a\n  (/ 1/0))");
+    assert_eq!(diagnostic.message, "invalid number `1/0`: zero denominator");
+    assert_eq!((diagnostic.line, diagnostic.column), (2, 6));
+}
+
+#[test]
+fn read_error_exact_exponent_too_large() {
+    let diagnostic = read_error("#e1e999999999");
+    assert_eq!(
+        diagnostic.message,
+        "invalid number `#e1e999999999`: exponent too large for an exact number"
+    );
+    let diagnostic = read_error("#e1.5e-99999999999999999999");
+    assert_eq!(
+        diagnostic.message,
+        "invalid number `#e1.5e-99999999999999999999`: exponent too large for an exact number"
+    );
+}
+
+#[test]
+fn read_error_prefix_without_number() {
+    for atom in ["#x", "#x1.5", "#b102", "#e#i1", "#e+inf.0", "#d1/"] {
+        let diagnostic = read_error(&format!("(a {})", atom));
+        assert_eq!(diagnostic.message, format!("invalid number `{}`", atom));
+        assert_eq!((diagnostic.line, diagnostic.column), (1, 4));
+    }
+}
+
+#[test]
+fn read_error_datum_label() {
+    for (input, label) in [("#0=(a . #0#)", "#0="), ("(a #12#)", "#12#"), ("#1=a", "#1=")] {
+        let diagnostic = read_error(input);
+        assert_eq!(
+            diagnostic.message,
+            format!("datum labels such as `{}` are not supported", label)
+        );
+    }
+}
+
+#[test]
+fn read_error_dot_without_head() {
+    let diagnostic = read_error("(. a)");
+    assert_eq!((diagnostic.line, diagnostic.column), (1, 1));
+}
+
+#[test]
+fn read_error_quote_without_datum() {
+    assert!(read_lisp(&mut "(a ')".as_bytes()).is_err());
+}
+
+#[test]
+fn read_error_unterminated_block_comment() {
+    let diagnostic = read_error("(a\n  #| b #| c |# d)");
+    assert_eq!(
+        diagnostic.message,
+        "unterminated block comment starting at 2:3"
+    );
+}
+
+#[test]
+fn read_error_datum_comment_without_datum() {
+    let diagnostic = read_error("(a #;)");
+    assert_eq!(diagnostic.message, "`#;` is not followed by a datum");
+    assert_eq!((diagnostic.line, diagnostic.column), (1, 4));
+}
+
+#[test]
+fn read_error_unknown_character() {
+    let diagnostic = read_error("(a #\\spaces)");
+    assert_eq!(diagnostic.message, "unknown character `#\\spaces`");
+    assert_eq!((diagnostic.line, diagnostic.column), (1, 4));
+}
+
+#[test]
+fn read_error_unclosed_vector() {
+    let diagnostic = read_error("#(1 #\\) 2");
+    assert_eq!(diagnostic.message, "unclosed `(` opened at 1:2");
+}
+
+#[test]
+fn read_error_malformed_hex_escape() {
+    let diagnostic = read_error(r#"(a "b\x4g;")"#);
+    assert_eq!(
+        diagnostic.message,
+        "invalid escape `\\x4g` in string: expected hex digits and `;`"
+    );
+    assert_eq!((diagnostic.line, diagnostic.column), (1, 6));
+}
+
+#[test]
+fn read_error_surrogate_escape() {
+    let diagnostic = read_error(r#""\xD800;""#);
+    assert_eq!(
+        diagnostic.message,
+        "invalid escape `\\xD800;` in string: not a Unicode scalar value"
+    );
+}
+
+#[test]
+fn read_error_unbraced_unicode_escape() {
+    let diagnostic = read_error(r#""\u3bb""#);
+    assert_eq!(
+        diagnostic.message,
+        "invalid escape `\\u` in string: expected `{`, as in `\\u{3bb}`"
+    );
+}
+
+#[test]
+fn read_error_text_after_continuation() {
+    let diagnostic = read_error("\"a\\ b\"");
+    assert_eq!(
+        diagnostic.message,
+        "invalid escape `\\ ` in string: only spaces may follow `\\` at the end of a line"
+    );
+}
+
+#[test]
+fn reader_spans_match_whole_input() {
+    let input = "; one\n(define x\n  \"two\nlines\") 'y #| three\n|# (z)\n";
+    let streamed: Vec<_> = Reader::new(input.as_bytes(), Some("test.lisp"))
+        .collect::<Result<_>>()
+        .unwrap();
+    let whole = read_lisp_spanned(&mut input.as_bytes(), Some("test.lisp")).unwrap();
+    assert_eq!(streamed, whole);
+}
+
+#[test]
+fn reader_yields_forms_before_an_error() {
+    let mut reader = Reader::new("(a)\n(b))\n(c)".as_bytes(), None);
+    assert_eq!(
+        reader.next().unwrap().unwrap().token,
+        vec![Symbol { value: "a".into() }].into()
+    );
+    assert_eq!(
+        reader.next().unwrap().unwrap().token,
+        vec![Symbol { value: "b".into() }].into()
+    );
+    match reader.next() {
+        Some(Err(Error::ReadError(diagnostic))) => {
+            assert_eq!(diagnostic.message, "unexpected `)` with no matching `(`");
+            assert_eq!((diagnostic.line, diagnostic.column), (2, 4));
+        }
+        other => panic!("expected a read error, got {:?}", other),
+    }
+    assert!(reader.next().is_none());
+}
+
+#[test]
+fn reader_continues_prefix_on_next_line() {
+    let tokens: Vec<Token> = Reader::new("'\n  x".as_bytes(), None)
+        .map(|spanned| spanned.unwrap().token)
+        .collect();
+    assert_eq!(
+        tokens,
+        vec![vec![
+            Symbol {
+                value: "quote".into()
+            },
+            Symbol { value: "x".into() }
+        ]
+        .into()]
+    );
+}
+
+#[test]
+fn reader_unexpected_eof() {
+    let mut reader = Reader::new("(a)\n(b\n  (c \"d".as_bytes(), Some("test.lisp"));
+    assert!(reader.next().unwrap().is_ok());
+    match reader.next() {
+        Some(Err(Error::UnexpectedEof(diagnostic))) => {
+            assert_eq!(diagnostic.message, "unterminated string starting at 3:6");
+            assert_eq!(diagnostic.source_line, "  (c \"d");
+        }
+        other => panic!("expected an unexpected end of input, got {:?}", other),
+    }
+    assert!(reader.next().is_none());
+}
+
+#[test]
+fn reader_empty_input() {
+    assert!(Reader::new(" \n\n".as_bytes(), None).next().is_none());
+}
+
+#[test]
+fn reader_long_string() {
+    let lines = "a\n".repeat(20_000);
+    let input = format!("\"{}\" b\n", lines);
+    let tokens: Vec<Token> = Reader::new(input.as_bytes(), None)
+        .map(|spanned| spanned.unwrap().token)
+        .collect();
+    assert_eq!(tokens, vec![lines.into(), Symbol { value: "b".into() }]);
+}
@@ -0,0 +1,97 @@
+//! An interactive read-eval-print loop.
+//!
+//! Each form is evaluated as soon as a line completes it, so a definition can
+//! be typed over several lines. Lines starting with a comma, outside of any
+//! form, are meta-commands:
+//!
+//! * `,quit` leaves the session;
+//! * `,env` lists the variables defined in the global environment;
+//! * `,tokens` prints the reader's tokens for the last form as JSON.
+
+use std::io::{self, BufRead, Write};
+
+use crate::error::Result;
+use crate::eval::Interpreter;
+use crate::reader::Reader;
+use crate::token::Token;
+use crate::value::Value;
+
+const PROMPT: &str = "> ";
+const CONTINUATION_PROMPT: &str = "... ";
+
+pub fn repl<R: BufRead, W: Write>(
+    interp: &mut Interpreter,
+    input: &mut R,
+    output: &mut W,
+) -> Result<()> {
+    let mut reader = Reader::new(io::empty(), None);
+    let mut last_form: Option<Token> = None;
+
+    loop {
+        write!(
+            output,
+            "{}",
+            if reader.is_pending() {
+                CONTINUATION_PROMPT
+            } else {
+                PROMPT
+            }
+        )?;
+        output.flush()?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            writeln!(output)?;
+            break;
+        }
+
+        if !reader.is_pending() {
+            match line.trim() {
+                "" => continue,
+                ",quit" => break,
+                ",env" => {
+                    for (name, value) in interp.global_bindings() {
+                        if !matches!(value, Value::Primitive(_)) {
+                            writeln!(output, "{} = {}", name, interp.print(&value))?;
+                        }
+                    }
+                    continue;
+                }
+                ",tokens" => {
+                    if let Some(ref token) = last_form {
+                        writeln!(output, "{}", serde_json::to_string(token)?)?;
+                    }
+                    continue;
+                }
+                command if command.starts_with(',') => {
+                    writeln!(output, "unknown command: {}", command)?;
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        reader.push_line(&line);
+        loop {
+            let token = match reader.read_buffered() {
+                Ok(Some(spanned)) => spanned.token,
+                Ok(None) => break,
+                Err(err) => {
+                    writeln!(output, "error: {}", err)?;
+                    reader.clear();
+                    break;
+                }
+            };
+            if token.is_comment() {
+                continue;
+            }
+            match interp.eval_toplevel(&token) {
+                Ok(value) => writeln!(output, "{}", interp.print(&value))?,
+                Err(err) => writeln!(output, "error: {}", err)?,
+            }
+            last_form = Some(token);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,216 @@
+//! Interned symbols.
+//!
+//! Every symbol name is stored once in a global [`SymbolTable`], and symbols
+//! are passed around as small [`Symbol`] ids, so comparing two symbols (in
+//! environment lookups or `eq?`) is an integer comparison.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+
+use lazy_static::lazy_static;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::reader;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Symbol(u32);
+
+// Symbols the evaluator dispatches on. They are interned first, in this order,
+// so their ids are known at compile time.
+pub const QUOTE: Symbol = Symbol(0);
+pub const IF: Symbol = Symbol(1);
+pub const BEGIN: Symbol = Symbol(2);
+pub const SET: Symbol = Symbol(3);
+pub const LAMBDA: Symbol = Symbol(4);
+pub const DEFINE: Symbol = Symbol(5);
+pub const FUNCTION: Symbol = Symbol(6);
+pub const QUASIQUOTE: Symbol = Symbol(7);
+pub const UNQUOTE: Symbol = Symbol(8);
+pub const UNQUOTE_SPLICING: Symbol = Symbol(9);
+pub const FLET: Symbol = Symbol(10);
+pub const LABELS: Symbol = Symbol(11);
+pub const DYNAMIC_LET: Symbol = Symbol(12);
+pub const DYNAMIC: Symbol = Symbol(13);
+pub const DYNAMIC_SET: Symbol = Symbol(14);
+pub const DEFVAR: Symbol = Symbol(15);
+pub const LET: Symbol = Symbol(16);
+pub const LETREC: Symbol = Symbol(17);
+pub const LETREC_STAR: Symbol = Symbol(18);
+pub const LET_STAR: Symbol = Symbol(19);
+pub const COND: Symbol = Symbol(20);
+pub const CASE: Symbol = Symbol(21);
+pub const AND: Symbol = Symbol(22);
+pub const OR: Symbol = Symbol(23);
+pub const WHEN: Symbol = Symbol(24);
+pub const UNLESS: Symbol = Symbol(25);
+pub const DO: Symbol = Symbol(26);
+pub const ELSE: Symbol = Symbol(27);
+pub const ARROW: Symbol = Symbol(28);
+// Temporaries introduced by the derived forms. Their names contain a space,
+// which no symbol read from source can, so they never capture a variable of
+// the program.
+pub const OR_VALUE: Symbol = Symbol(29);
+pub const COND_VALUE: Symbol = Symbol(30);
+pub const CASE_KEY: Symbol = Symbol(31);
+
+pub const CATCH: Symbol = Symbol(32);
+pub const THROW: Symbol = Symbol(33);
+// The primitives the derived forms call, bound under names that contain a
+// space too, so that a program binding `eqv?` or `funcall` itself does not
+// change what `case` and `=>` do.
+pub const EQV: Symbol = Symbol(34);
+pub const FUNCALL: Symbol = Symbol(35);
+
+const WELL_KNOWN: &[&str] = &[
+    "quote",
+    "if",
+    "begin",
+    "set!",
+    "lambda",
+    "define",
+    "function",
+    "quasiquote",
+    "unquote",
+    "unquote-splicing",
+    "flet",
+    "labels",
+    "dynamic-let",
+    "dynamic",
+    "dynamic-set!",
+    "defvar",
+    "let",
+    "letrec",
+    "letrec*",
+    "let*",
+    "cond",
+    "case",
+    "and",
+    "or",
+    "when",
+    "unless",
+    "do",
+    "else",
+    "=>",
+    "or value",
+    "cond value",
+    "case key",
+    "catch",
+    "throw",
+    "derived eqv?",
+    "derived funcall",
+];
+
+/// Maps symbol names to ids and back.
+pub struct SymbolTable {
+    names: Vec<&'static str>,
+    ids: HashMap<&'static str, Symbol>,
+}
+
+impl SymbolTable {
+    fn new() -> Self {
+        let mut table = SymbolTable {
+            names: Vec::new(),
+            ids: HashMap::new(),
+        };
+        for name in WELL_KNOWN {
+            table.intern(name);
+        }
+        table
+    }
+
+    fn intern(&mut self, name: &str) -> Symbol {
+        if let Some(symbol) = self.ids.get(name) {
+            return *symbol;
+        }
+        // Names live as long as the table, which lives as long as the program.
+        let name: &'static str = Box::leak(name.to_string().into_boxed_str());
+        let symbol = Symbol(self.names.len() as u32);
+        self.names.push(name);
+        self.ids.insert(name, symbol);
+        symbol
+    }
+}
+
+lazy_static! {
+    static ref SYMBOLS: Mutex<SymbolTable> = Mutex::new(SymbolTable::new());
+}
+
+impl Symbol {
+    pub fn intern(name: &str) -> Symbol {
+        SYMBOLS.lock().unwrap().intern(name)
+    }
+
+    pub fn name(self) -> &'static str {
+        SYMBOLS.lock().unwrap().names[self.0 as usize]
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(value: &str) -> Self {
+        Symbol::intern(value)
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.name())
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl Serialize for Symbol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+/// Only accepts names that read back as the same symbol, which keeps out
+/// names like `12` or `#t` as well as the evaluator's temporaries.
+impl<'de> Deserialize<'de> for Symbol {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        if !reader::is_symbol_name(&name) {
+            return Err(D::Error::custom(format!("invalid symbol {:?}", name)));
+        }
+        Ok(Symbol::intern(&name))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_intern_same_name() {
+        assert_eq!(Symbol::intern("foo"), Symbol::intern("foo"));
+    }
+
+    #[test]
+    fn test_intern_different_names() {
+        assert_ne!(Symbol::intern("foo"), Symbol::intern("bar"));
+    }
+
+    #[test]
+    fn test_name() {
+        assert_eq!(Symbol::intern("a-symbol").name(), "a-symbol");
+    }
+
+    #[test]
+    fn test_well_known() {
+        for name in WELL_KNOWN {
+            assert_eq!(Symbol::intern(name).name(), *name);
+        }
+        assert_eq!(Symbol::intern("quote"), QUOTE);
+        assert_eq!(Symbol::intern("function"), FUNCTION);
+        assert_eq!(Symbol::intern("unquote-splicing"), UNQUOTE_SPLICING);
+    }
+}
@@ -1,29 +1,285 @@
-use serde::Serialize;
+use std::fmt;
+
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{One, ToPrimitive};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::error::{Error, Result};
+use crate::printer::{abbreviation, write_char, write_float, write_string};
+use crate::reader;
+use crate::symbol::Symbol;
 
-#[derive(Debug, Serialize, PartialEq, Clone)]
-#[serde(tag = "type")]
+// The derived implementations are inherent functions, wrapped by the trait
+// implementations below so that deserialized tokens are checked.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(tag = "type", remote = "Self")]
 pub enum Token {
-    Integer { value: i64 },
-    Float { value: f64 },
-    Rational { numerator: i64, denominator: i64 },
-    String { value: String },
-    Symbol { value: String },
+    Integer {
+        value: i64,
+    },
+    /// An integer too large for an `i64`.
+    BigInteger {
+        #[serde(
+            serialize_with = "serialize_bigint",
+            deserialize_with = "deserialize_bigint"
+        )]
+        value: BigInt,
+    },
+    Float {
+        #[serde(
+            serialize_with = "serialize_float",
+            deserialize_with = "deserialize_float"
+        )]
+        value: f64,
+    },
+    /// A fraction in lowest terms, with the sign on the numerator and a
+    /// denominator greater than 1.
+    Rational {
+        #[serde(
+            serialize_with = "serialize_bigint",
+            deserialize_with = "deserialize_bigint"
+        )]
+        numerator: BigInt,
+        #[serde(
+            serialize_with = "serialize_bigint",
+            deserialize_with = "deserialize_bigint"
+        )]
+        denominator: BigInt,
+    },
+    String {
+        value: String,
+    },
+    Symbol {
+        value: Symbol,
+    },
+    Boolean {
+        value: bool,
+    },
+    Character {
+        value: char,
+    },
+    Vector {
+        items: Vec<Token>,
+    },
     ListStart,
     ListEnd,
     EmptyList,
-    Cons { head: Box<Token>, tail: Box<Token> },
+    Cons {
+        head: Box<Token>,
+        tail: Box<Token>,
+    },
     Nil,
     Dot,
-    Comment { depth: usize, comment: String },
+    Comment {
+        depth: usize,
+        comment: String,
+    },
+    /// The text between `#|` and `|#`, including any nested block comments.
+    BlockComment {
+        comment: String,
+    },
+    /// A datum commented out with `#;`.
+    DatumComment {
+        datum: Box<Token>,
+    },
+}
+
+impl Serialize for Token {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        Token::serialize(self, serializer)
+    }
+}
+
+/// Only accepts tokens the reader could have produced, so that printing a
+/// deserialized token gives source that reads back as the same token: a
+/// comment has to end where it is printed to, an integer that fits in an
+/// `i64` is an `Integer` and a symbol's name cannot read as anything else.
+/// Nested tokens are checked as they are deserialized.
+impl<'de> Deserialize<'de> for Token {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let token = Token::deserialize(deserializer)?;
+        token.check().map_err(D::Error::custom)?;
+        Ok(token)
+    }
+}
+
+/// The characters that have names, as in `#\space`.
+pub const CHARACTER_NAMES: &[(&str, char)] = &[
+    ("alarm", '\u{7}'),
+    ("backspace", '\u{8}'),
+    ("delete", '\u{7f}'),
+    ("escape", '\u{1b}'),
+    ("newline", '\n'),
+    ("null", '\0'),
+    ("return", '\r'),
+    ("space", ' '),
+    ("tab", '\t'),
+];
+
+/// Writes integers that fit in an `i64` as JSON numbers, and larger ones as
+/// strings so that JSON readers do not round them.
+fn serialize_bigint<S: Serializer>(
+    value: &BigInt,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    match value.to_i64() {
+        Some(value) => serializer.serialize_i64(value),
+        None => serializer.collect_str(value),
+    }
+}
+
+/// Reads integers written by [`serialize_bigint`], as either JSON numbers or
+/// strings.
+fn deserialize_bigint<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<BigInt, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Written {
+        Number(i64),
+        String(String),
+    }
+    match Written::deserialize(deserializer)? {
+        Written::Number(value) => Ok(value.into()),
+        Written::String(text) => text
+            .parse()
+            .map_err(|_| D::Error::custom(format!("invalid integer {:?}", text))),
+    }
+}
+
+/// Writes finite floats as JSON numbers, and infinities and NaN, which JSON
+/// has no numbers for, as strings in the reader's syntax.
+fn serialize_float<S: Serializer>(
+    value: &f64,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    if value.is_nan() {
+        serializer.serialize_str("+nan.0")
+    } else if value.is_infinite() {
+        serializer.serialize_str(if *value > 0.0 { "+inf.0" } else { "-inf.0" })
+    } else {
+        serializer.serialize_f64(*value)
+    }
+}
+
+/// Reads floats written by [`serialize_float`], as either JSON numbers or
+/// strings.
+fn deserialize_float<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<f64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Written {
+        Number(f64),
+        String(String),
+    }
+    match Written::deserialize(deserializer)? {
+        Written::Number(value) => Ok(value),
+        Written::String(text) => match text.as_str() {
+            "+nan.0" => Ok(f64::NAN),
+            "+inf.0" => Ok(f64::INFINITY),
+            "-inf.0" => Ok(f64::NEG_INFINITY),
+            _ => Err(D::Error::custom(format!("invalid float {:?}", text))),
+        },
+    }
+}
+
+/// Where a token was read from. Offsets are in bytes; lines and columns start
+/// at 1, and columns count characters. The end of a span is exclusive.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Span {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+    /// The spans of the data inside the token: a list's elements followed by
+    /// the tail after its dot, if it has one, a vector's items, or the datum
+    /// after a prefix such as `'` or `#;`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub items: Vec<Span>,
+}
+
+/// A token together with its location in the source.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Spanned {
+    #[serde(flatten)]
+    pub token: Token,
+    pub span: Span,
 }
 
 impl Token {
+    /// Finds what is wrong with a token built by hand rather than read: what
+    /// the reader would never produce, or what would print as source that
+    /// reads back differently. The tokens it contains are assumed to be
+    /// valid themselves.
+    fn check(&self) -> std::result::Result<(), String> {
+        match self {
+            Token::ListStart | Token::ListEnd => {
+                Err(format!("{:?} is only used inside the reader", self))
+            }
+            Token::Rational {
+                numerator,
+                denominator,
+            } => {
+                if *denominator <= BigInt::one() {
+                    Err(format!("invalid denominator {}", denominator))
+                } else if !numerator.gcd(denominator).is_one() {
+                    Err(format!(
+                        "{}/{} is not in lowest terms",
+                        numerator, denominator
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+            Token::BigInteger { value } if value.to_i64().is_some() => {
+                Err(format!("{} fits in an integer", value))
+            }
+            // Comments hold text, which must not run past their end.
+            Token::Comment { .. } | Token::BlockComment { .. }
+                if !reader::reads_as(&self.to_string(), self) =>
+            {
+                Err(format!("{:?} does not read back as itself", self))
+            }
+            Token::Cons { head, tail } => [head, tail].iter().try_for_each(|t| t.check_datum()),
+            Token::Vector { items } => items.iter().try_for_each(Token::check_datum),
+            Token::DatumComment { datum } => datum.check_datum(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails if the token is not a datum: a comment, or a dot outside of a
+    /// list's syntax.
+    fn check_datum(&self) -> std::result::Result<(), String> {
+        match self {
+            Token::Dot
+            | Token::Comment { .. }
+            | Token::BlockComment { .. }
+            | Token::DatumComment { .. } => Err(format!("{:?} cannot be part of a datum", self)),
+            _ => Ok(()),
+        }
+    }
+
     pub fn is_cons(&self) -> bool {
         matches!(self, Token::Cons { .. })
     }
 
+    /// Whether the token is a comment of any kind, which is trivia that
+    /// only tooling cares about.
+    pub fn is_comment(&self) -> bool {
+        matches!(
+            self,
+            Token::Comment { .. } | Token::BlockComment { .. } | Token::DatumComment { .. }
+        )
+    }
+
+    #[allow(clippy::replace_box)]
     pub fn set_tail(&mut self, tail: Token) -> Result<()> {
         match self {
             Token::Cons { tail: t, .. } => {
@@ -56,6 +312,65 @@ impl Token {
     }
 }
 
+/// Formats the token in the syntax the reader reads, abbreviating forms
+/// like `(quote x)`.
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Integer { value } => write!(f, "{}", value),
+            Token::BigInteger { value } => write!(f, "{}", value),
+            Token::Float { value } => write_float(f, *value),
+            Token::Rational {
+                numerator,
+                denominator,
+            } => write!(f, "{}/{}", numerator, denominator),
+            Token::String { value } => write_string(f, value),
+            Token::Symbol { value } => write!(f, "{}", value),
+            Token::Boolean { value: true } => write!(f, "#t"),
+            Token::Boolean { value: false } => write!(f, "#f"),
+            Token::Character { value } => write_char(f, *value),
+            Token::Vector { items } => {
+                write!(f, "#(")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, ")")
+            }
+            Token::ListStart => write!(f, "("),
+            Token::ListEnd => write!(f, ")"),
+            Token::EmptyList => write!(f, "()"),
+            Token::Cons { head, tail } => {
+                if let (Token::Symbol { value }, Token::Cons { head: datum, tail }) =
+                    (head.as_ref(), tail.as_ref())
+                {
+                    if let (Some(prefix), Token::EmptyList) = (abbreviation(*value), tail.as_ref())
+                    {
+                        return write!(f, "{}{}", prefix, datum);
+                    }
+                }
+                write!(f, "({}", head)?;
+                let mut cursor = tail.as_ref();
+                while let Token::Cons { head, tail } = cursor {
+                    write!(f, " {}", head)?;
+                    cursor = tail;
+                }
+                match cursor {
+                    Token::EmptyList => write!(f, ")"),
+                    tail => write!(f, " . {})", tail),
+                }
+            }
+            Token::Nil => write!(f, "nil"),
+            Token::Dot => write!(f, "."),
+            Token::Comment { depth, comment } => write!(f, "{}{}", ";".repeat(*depth), comment),
+            Token::BlockComment { comment } => write!(f, "#|{}|#", comment),
+            Token::DatumComment { datum } => write!(f, "#;{}", datum),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use pretty_assertions::assert_eq;
@@ -83,6 +398,173 @@ mod test {
         assert_eq!(token.is_cons(), false);
     }
 
+    #[test]
+    fn test_serialize_big_numbers() {
+        let big: BigInt = "123456789012345678901234567890".parse().unwrap();
+        let token = Token::Rational {
+            numerator: big.clone(),
+            denominator: BigInt::from(7),
+        };
+        assert_eq!(
+            serde_json::to_string(&token).unwrap(),
+            r#"{"type":"Rational","numerator":"123456789012345678901234567890","denominator":7}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&Token::BigInteger { value: big }).unwrap(),
+            r#"{"type":"BigInteger","value":"123456789012345678901234567890"}"#
+        );
+    }
+
+    #[test]
+    fn test_serialize_non_finite_floats() {
+        for (value, json) in [
+            (f64::INFINITY, r#"{"type":"Float","value":"+inf.0"}"#),
+            (f64::NEG_INFINITY, r#"{"type":"Float","value":"-inf.0"}"#),
+            (f64::NAN, r#"{"type":"Float","value":"+nan.0"}"#),
+            (-0.5, r#"{"type":"Float","value":-0.5}"#),
+        ] {
+            let token = Token::Float { value };
+            assert_eq!(serde_json::to_string(&token).unwrap(), json);
+            match serde_json::from_str(json).unwrap() {
+                Token::Float { value: read } => {
+                    assert_eq!(read.to_bits(), value.to_bits(), "{}", json)
+                }
+                other => panic!("expected a float, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_serialize_literals() {
+        let token = Token::Vector {
+            items: vec![
+                Token::Boolean { value: true },
+                Token::Character { value: 'a' },
+            ],
+        };
+        assert_eq!(
+            serde_json::to_string(&token).unwrap(),
+            r#"{"type":"Vector","items":[{"type":"Boolean","value":true},{"type":"Character","value":"a"}]}"#
+        );
+    }
+
+    #[test]
+    fn test_display_literals() {
+        let token = Token::Vector {
+            items: vec![
+                Token::Boolean { value: false },
+                Token::Character { value: ' ' },
+                Token::Character { value: '\u{1}' },
+                Token::Character { value: 'λ' },
+            ],
+        };
+        assert_eq!(token.to_string(), "#(#f #\\space #\\x1 #\\λ)");
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let big: BigInt = "-123456789012345678901234567890".parse().unwrap();
+        let token: Token = vec![
+            Token::Symbol { value: "a".into() },
+            Token::BigInteger { value: big },
+            Token::try_from((3, 4)).unwrap(),
+            Token::Vector {
+                items: vec![Token::Character { value: 'λ' }],
+            },
+        ]
+        .into();
+        let json = serde_json::to_string(&token).unwrap();
+        assert_eq!(serde_json::from_str::<Token>(&json).unwrap(), token);
+    }
+
+    #[test]
+    fn test_deserialize_spanned() {
+        let json = concat!(
+            r#"{"type":"Integer","value":42,"#,
+            r#""span":{"start":1,"end":3,"line":2,"column":1,"end_line":2,"end_column":3}}"#
+        );
+        let spanned: Spanned = serde_json::from_str(json).unwrap();
+        assert_eq!(spanned.token, Token::Integer { value: 42 });
+        assert_eq!(spanned.span.file, None);
+        assert_eq!(serde_json::to_string(&spanned).unwrap(), json);
+    }
+
+    #[test]
+    fn test_deserialize_invalid_bigint() {
+        let json = r#"{"type":"BigInteger","value":"12a"}"#;
+        assert!(serde_json::from_str::<Token>(json).is_err());
+    }
+
+    #[test]
+    fn test_deserialize_rejects_invalid_tokens() {
+        for json in [
+            r#"{"type":"ListEnd"}"#,
+            r#"{"type":"ListStart"}"#,
+            r#"{"type":"Cons","head":{"type":"Integer","value":1},"tail":{"type":"Dot"}}"#,
+            r#"{"type":"Cons","head":{"type":"Comment","depth":1,"comment":""},"tail":{"type":"EmptyList"}}"#,
+            r#"{"type":"Vector","items":[{"type":"Dot"}]}"#,
+            r#"{"type":"Rational","numerator":1,"denominator":0}"#,
+            r#"{"type":"Rational","numerator":1,"denominator":-2}"#,
+            r#"{"type":"Rational","numerator":3,"denominator":1}"#,
+            r#"{"type":"Rational","numerator":2,"denominator":4}"#,
+            r#"{"type":"Symbol","value":"or value"}"#,
+            r#"{"type":"Symbol","value":"a(b"}"#,
+            r#"{"type":"Symbol","value":""}"#,
+            r#"{"type":"Symbol","value":"123"}"#,
+            r##"{"type":"Symbol","value":"#t"}"##,
+            r#"{"type":"Symbol","value":"."}"#,
+            r#"{"type":"Symbol","value":"nil"}"#,
+            r#"{"type":"Symbol","value":"'a"}"#,
+            r#"{"type":"Comment","depth":0,"comment":"a"}"#,
+            r#"{"type":"Comment","depth":1,"comment":"a\nb"}"#,
+            r#"{"type":"Comment","depth":1,"comment":"a\rb"}"#,
+            r#"{"type":"Comment","depth":1,"comment":";a"}"#,
+            r##"{"type":"BlockComment","comment":"a |# b"}"##,
+            r##"{"type":"BlockComment","comment":"a #| b"}"##,
+            r##"{"type":"BlockComment","comment":"a#"}"##,
+            r#"{"type":"BigInteger","value":12}"#,
+            r#"{"type":"BigInteger","value":"-9223372036854775808"}"#,
+        ] {
+            assert!(serde_json::from_str::<Token>(json).is_err(), "{}", json);
+        }
+    }
+
+    #[test]
+    fn test_deserialize_comments() {
+        for token in [
+            Token::Comment {
+                depth: 2,
+                comment: " a; b".to_string(),
+            },
+            Token::BlockComment {
+                comment: " a #| b |# c|".to_string(),
+            },
+        ] {
+            let json = serde_json::to_string(&token).unwrap();
+            assert_eq!(serde_json::from_str::<Token>(&json).unwrap(), token);
+        }
+    }
+
+    #[test]
+    fn test_deserialize_dot() {
+        let json = r#"{"type":"Dot"}"#;
+        assert_eq!(serde_json::from_str::<Token>(json).unwrap(), Token::Dot);
+    }
+
+    #[test]
+    fn test_display_list() {
+        let token = Token::Cons {
+            head: Box::new(Token::Symbol { value: "a".into() }),
+            tail: Box::new(Token::Cons {
+                head: Box::new(Token::Float { value: 2.0 }),
+                tail: Box::new(Token::String {
+                    value: "c".to_string(),
+                }),
+            }),
+        };
+        assert_eq!(token.to_string(), "(a 2.0 . \"c\")");
+    }
+
     #[test]
     fn test_set_tail_not_cons() {
         let mut token = Token::Integer { value: 1 };
@@ -0,0 +1,237 @@
+//! Runtime values.
+//!
+//! The reader produces [`Token`]s, which include lexical artifacts such as
+//! [`Token::ListEnd`] that can never be the result of an evaluation. The
+//! evaluator works with [`Value`]s instead, converting quoted data from the
+//! reader with [`Heap::alloc_datum`]. Values that can take part in cycles
+//! (pairs, vectors and procedures) are handles to objects on the [`Heap`].
+
+use std::fmt;
+use std::rc::Rc;
+
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::Zero;
+
+use crate::error::{Error, Result};
+use crate::eval::Interpreter;
+use crate::heap::{Heap, Ref};
+use crate::number::Number;
+use crate::printer::{Printed, Style};
+use crate::symbol::Symbol;
+use crate::token::Token;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Integer(i64),
+    /// An integer too large for an `i64`.
+    BigInteger(Rc<BigInt>),
+    /// A rational in lowest terms whose denominator is not 1.
+    Rational(Rc<BigRational>),
+    Float(f64),
+    String(Rc<str>),
+    Symbol(Symbol),
+    Pair(Ref),
+    EmptyList,
+    Boolean(bool),
+    Character(char),
+    Vector(Ref),
+    /// A procedure created by `lambda`.
+    Procedure(Ref),
+    /// A procedure implemented in Rust.
+    Primitive(Primitive),
+    /// The value of a `letrec` variable or internal definition before it is
+    /// initialized. Looking such a variable up is an error, so programs never
+    /// see it.
+    Unassigned,
+}
+
+/// A built-in procedure.
+#[derive(Clone)]
+pub struct Primitive {
+    pub name: &'static str,
+    pub(crate) func: fn(&mut Interpreter, Vec<Value>) -> Result<Value>,
+}
+
+impl Primitive {
+    pub(crate) const fn new(
+        name: &'static str,
+        func: fn(&mut Interpreter, Vec<Value>) -> Result<Value>,
+    ) -> Self {
+        Primitive { name, func }
+    }
+}
+
+impl PartialEq for Primitive {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl fmt::Debug for Primitive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Primitive({:?})", self.name)
+    }
+}
+
+impl Value {
+    pub fn symbol(name: &str) -> Value {
+        Value::Symbol(Symbol::intern(name))
+    }
+
+    /// Converts a token that is not a list into a value.
+    pub fn atom(token: &Token) -> Result<Value> {
+        match token {
+            Token::Integer { value } => Ok(Value::Integer(*value)),
+            Token::BigInteger { value } => Ok(Number::from(value.clone()).into()),
+            Token::Float { value } => Ok(Value::Float(*value)),
+            Token::Rational {
+                numerator,
+                denominator,
+            } => {
+                if denominator.is_zero() {
+                    return Err(Error::InvalidTokenOperation(format!(
+                        "{} has a zero denominator",
+                        token
+                    )));
+                }
+                let ratio = BigRational::new(numerator.clone(), denominator.clone());
+                Ok(Number::from(ratio).into())
+            }
+            Token::String { value } => Ok(Value::String(Rc::from(value.as_str()))),
+            Token::Symbol { value } => Ok(Value::Symbol(*value)),
+            Token::Boolean { value } => Ok(Value::Boolean(*value)),
+            Token::Character { value } => Ok(Value::Character(*value)),
+            Token::EmptyList => Ok(Value::EmptyList),
+            Token::Nil => Ok(Value::Boolean(false)),
+            Token::Cons { .. }
+            | Token::Vector { .. }
+            | Token::ListStart
+            | Token::ListEnd
+            | Token::Dot
+            | Token::Comment { .. }
+            | Token::BlockComment { .. }
+            | Token::DatumComment { .. } => Err(Error::InvalidTokenOperation(format!(
+                "{:?} is not an atom",
+                token
+            ))),
+        }
+    }
+
+    /// The value of expressions, like `(if #f #f)`, whose value the language
+    /// leaves unspecified.
+    pub fn unspecified() -> Value {
+        Value::Boolean(false)
+    }
+
+    /// Only `#f` counts as false.
+    pub fn is_false(&self) -> bool {
+        matches!(self, Value::Boolean(false))
+    }
+
+    /// The heap object this value refers to, if any.
+    pub fn heap_ref(&self) -> Option<Ref> {
+        match self {
+            Value::Pair(r) | Value::Vector(r) | Value::Procedure(r) => Some(*r),
+            _ => None,
+        }
+    }
+
+    /// Identity comparison, as in `eq?`.
+    pub fn is_eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::String(a), Value::String(b)) => Rc::ptr_eq(a, b),
+            (a, b) => a == b,
+        }
+    }
+
+    /// Equivalence, as in `eqv?`: identity, except that numbers of the same
+    /// exactness are compared by value. Floats are compared bit by bit, so
+    /// `0.0` and `-0.0` differ while a NaN is equivalent to itself.
+    pub fn is_eqv(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Float(a), Value::Float(b)) => a.to_bits() == b.to_bits(),
+            (a, b) => a.is_eq(b),
+        }
+    }
+
+    /// Returns an object that formats this value as `write` does, in syntax
+    /// the reader can read back.
+    pub fn write<'a>(&'a self, heap: &'a Heap) -> Printed<'a> {
+        Printed::new(self, heap, Style::Write)
+    }
+
+    /// Returns an object that formats this value as `display` does, with
+    /// strings and characters printed as their contents.
+    pub fn display<'a>(&'a self, heap: &'a Heap) -> Printed<'a> {
+        Printed::new(self, heap, Style::Display)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Integer(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Boolean(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(Rc::from(value))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_atom_nil() {
+        assert_eq!(Value::atom(&Token::Nil).unwrap(), Value::Boolean(false));
+    }
+
+    #[test]
+    fn test_atom_lexical_token() {
+        assert!(Value::atom(&Token::ListEnd).is_err());
+        assert!(Value::atom(&Token::Dot).is_err());
+    }
+
+    #[test]
+    fn test_is_eq_strings() {
+        let a: Value = "a".into();
+        assert!(a.is_eq(&a.clone()));
+        assert!(!a.is_eq(&"a".into()));
+    }
+
+    #[test]
+    fn test_write() {
+        let mut heap = Heap::default();
+        let vector = heap.vector(vec![1.into(), 2.5.into()]);
+        let half = Number::from(BigRational::new(1.into(), 2.into()));
+        let pair = heap.cons("b".into(), half.into());
+        let value = heap.list(vec![
+            Value::symbol("a"),
+            Value::Boolean(true),
+            Value::Character(' '),
+            vector,
+            pair,
+        ]);
+        assert_eq!(
+            value.write(&heap).to_string(),
+            "(a #t #\\space #(1 2.5) (\"b\" . 1/2))"
+        );
+    }
+}
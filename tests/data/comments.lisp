@@ -0,0 +1,3 @@
+#| note |#
+#;(skipped)
+kept
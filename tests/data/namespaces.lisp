@@ -0,0 +1,4 @@
+; A Lisp-1 calls the parameter `f`; a Lisp-2 calls the global function `f`.
+(define (f) 'function)
+(define (call f) (f))
+(call (lambda () 'argument))
@@ -0,0 +1,6 @@
+(define (f x)
+  (if (< x 2)
+      x
+      (f (- x 1)))
+
+(f 3
@@ -4,6 +4,7 @@ use pretty_assertions::*;
 use std::{path::Path, process::Command};
 
 #[test]
+#[allow(clippy::needless_borrows_for_generic_args)]
 fn test_main() {
     // Requires an input parameter.
     Command::cargo_bin(env!("CARGO_PKG_NAME"))
@@ -40,7 +41,11 @@ fn test_empty_file() {
 fn test_parse_integer() {
     test_parse_file(
         "tests/data/integer.lisp",
-        "{\"type\":\"Integer\",\"value\":42}",
+        concat!(
+            "{\"type\":\"Integer\",\"value\":42,",
+            "\"span\":{\"file\":\"tests/data/integer.lisp\",\"start\":1,\"end\":3,",
+            "\"line\":2,\"column\":1,\"end_line\":2,\"end_column\":3}}"
+        ),
     );
 }
 
@@ -48,7 +53,11 @@ fn test_parse_integer() {
 fn test_parse_symbol() {
     test_parse_file(
         "tests/data/symbol.lisp",
-        "{\"type\":\"Symbol\",\"value\":\"foobar\"}",
+        concat!(
+            "{\"type\":\"Symbol\",\"value\":\"foobar\",",
+            "\"span\":{\"file\":\"tests/data/symbol.lisp\",\"start\":1,\"end\":7,",
+            "\"line\":2,\"column\":1,\"end_line\":2,\"end_column\":7}}"
+        ),
     );
 }
 
@@ -56,6 +65,299 @@ fn test_parse_symbol() {
 fn test_parse_float() {
     test_parse_file(
         "tests/data/float.lisp",
-        "{\"type\":\"Float\",\"value\":3.14159}",
+        concat!(
+            "{\"type\":\"Float\",\"value\":3.14159,",
+            "\"span\":{\"file\":\"tests/data/float.lisp\",\"start\":1,\"end\":8,",
+            "\"line\":2,\"column\":1,\"end_line\":2,\"end_column\":8}}"
+        ),
     );
 }
+
+#[test]
+fn test_parse_list() {
+    test_parse_file(
+        "tests/data/list.lisp",
+        concat!(
+            "{\"type\":\"Cons\",\"head\":{\"type\":\"Symbol\",\"value\":\"a\"},",
+            "\"tail\":{\"type\":\"Cons\",\"head\":{\"type\":\"Integer\",\"value\":1},",
+            "\"tail\":{\"type\":\"EmptyList\"}},",
+            "\"span\":{\"file\":\"tests/data/list.lisp\",\"start\":0,\"end\":5,",
+            "\"line\":1,\"column\":1,\"end_line\":1,\"end_column\":6,\"items\":[",
+            "{\"file\":\"tests/data/list.lisp\",\"start\":1,\"end\":2,",
+            "\"line\":1,\"column\":2,\"end_line\":1,\"end_column\":3},",
+            "{\"file\":\"tests/data/list.lisp\",\"start\":3,\"end\":4,",
+            "\"line\":1,\"column\":4,\"end_line\":1,\"end_column\":5}]}}"
+        ),
+    );
+}
+
+#[test]
+fn test_parse_skips_comments() {
+    test_parse_file(
+        "tests/data/comments.lisp",
+        concat!(
+            "{\"type\":\"Symbol\",\"value\":\"kept\",",
+            "\"span\":{\"file\":\"tests/data/comments.lisp\",\"start\":23,\"end\":27,",
+            "\"line\":3,\"column\":1,\"end_line\":3,\"end_column\":5}}"
+        ),
+    );
+}
+
+#[test]
+fn test_parse_trivia() {
+    let assert = Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .args(["parse", "--input", "tests/data/comments.lisp", "--trivia"])
+        .assert()
+        .success();
+    let output = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+    let types: Vec<&str> = output
+        .lines()
+        .map(|line| line.split('"').nth(3).unwrap())
+        .collect();
+    assert_str_eq!(types.join(" "), "BlockComment DatumComment Symbol");
+}
+
+#[test]
+fn test_unparse() {
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .args(["unparse", "--input", "tests/data/tokens.jsonl"])
+        .assert()
+        .success()
+        .stdout(concat!(
+            ";; written by another tool\n",
+            "(define big 123456789012345678901234567890)\n",
+            "'#(\"a\\nb\" #\\space -1/3)\n",
+        ));
+}
+
+#[test]
+fn test_unparse_parsed_file() {
+    let parsed = Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .args(["parse", "--input", "tests/data/fib-call.scheme"])
+        .output()
+        .unwrap();
+    let unparsed = assert_cmd::Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .args(["unparse", "--input", "-"])
+        .write_stdin(parsed.stdout)
+        .output()
+        .unwrap();
+    assert_cmd::Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .args(["run", "--input", "-"])
+        .write_stdin(unparsed.stdout)
+        .assert()
+        .success()
+        .stdout("55\n");
+}
+
+#[test]
+fn test_unparse_invalid_json() {
+    assert_cmd::Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .args(["unparse", "--input", "-"])
+        .write_stdin("{\"type\":\"Integer\",\"value\":1}\n{\"type\":\"Integer\"}\n")
+        .assert()
+        .failure()
+        .stdout("1\n")
+        .stderr(predicates::str::contains("missing field `value` at line 2"));
+}
+
+fn test_run_file<P: AsRef<Path>, S: AsRef<str>>(input: P, args: &[&str], expected: S) {
+    let assert = Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .args(["run", "--input", &input.as_ref().to_string_lossy()])
+        .args(args)
+        .assert()
+        .success();
+    let output = assert.get_output();
+    let actual = String::from_utf8(output.stdout.clone())
+        .unwrap()
+        .trim()
+        .to_string();
+    assert_str_eq!(actual, expected.as_ref().to_string());
+}
+
+#[test]
+fn test_run_fib() {
+    test_run_file("tests/data/fib-call.scheme", &[], "55");
+}
+
+#[test]
+fn test_run_fib_commented() {
+    test_run_file("tests/data/fib-commented.scheme", &[], "55");
+}
+
+#[test]
+fn test_run_fib_block_comments() {
+    test_run_file("tests/data/fib-block-comments.scheme", &[], "55");
+}
+
+#[test]
+fn test_run_fib_nested() {
+    test_run_file(
+        "tests/data/fib-nested.scheme",
+        &[],
+        "(55 34 21 13 8 5 3 2 1 1)",
+    );
+}
+
+#[test]
+fn test_run_fib_all() {
+    test_run_file("tests/data/fib-call.scheme", &["--all"], "fib\n55");
+}
+
+#[test]
+fn test_run_empty_file() {
+    test_run_file("tests/data/empty.lisp", &[], "");
+}
+
+#[test]
+fn test_run_stdin() {
+    assert_cmd::Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .args(["run", "--input", "-", "--all"])
+        .write_stdin("(define x\n  2)\n(* x 3)\n")
+        .assert()
+        .success()
+        .stdout("x\n6\n");
+}
+
+#[test]
+fn test_run_lisp1() {
+    test_run_file("tests/data/namespaces.lisp", &[], "argument");
+}
+
+#[test]
+fn test_run_lisp2() {
+    test_run_file("tests/data/namespaces.lisp", &["--lisp2"], "function");
+}
+
+#[test]
+fn test_run_search() {
+    test_run_file("tests/data/search.scheme", &[], "(3 none)");
+}
+
+#[test]
+fn test_run_uncaught_throw() {
+    assert_cmd::Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .args(["run", "--input", "-"])
+        .write_stdin("(throw 'nowhere 1)\n")
+        .assert()
+        .failure()
+        .stderr("error: -:1:1: no catch for tag nowhere\n");
+}
+
+#[test]
+fn test_run_unbound_variable() {
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .args(["run", "--input", "tests/data/unbound.lisp"])
+        .assert()
+        .failure()
+        .stderr("error: tests/data/unbound.lisp:3:1: unbound variable: y\n");
+}
+
+#[test]
+fn test_run_unclosed_list() {
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .args(["run", "--input", "tests/data/unclosed.lisp"])
+        .assert()
+        .failure()
+        .stderr(concat!(
+            "error: unclosed `(` opened at 6:1\n",
+            " --> tests/data/unclosed.lisp:6:1\n",
+            "  |\n",
+            "6 | (f 3\n",
+            "  | ^\n",
+        ));
+}
+
+fn test_repl<S: AsRef<str>>(input: &str, expected: S) {
+    let assert = assert_cmd::Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .arg("repl")
+        .write_stdin(input)
+        .assert()
+        .success();
+    let output = assert.get_output();
+    let actual = String::from_utf8(output.stdout.clone()).unwrap();
+    assert_str_eq!(actual, expected.as_ref().to_string());
+}
+
+#[test]
+fn test_repl_multi_line() {
+    test_repl(
+        "(define (square x)\n  (* x x))\n(square 12)\n",
+        "> ... square\n> 144\n> \n",
+    );
+}
+
+#[test]
+fn test_repl_keeps_environment_after_error() {
+    test_repl(
+        "(define x 2)\n(+ x y)\n(+ x 1)\n,quit\n",
+        "> x\n> error: unbound variable: y\n> 3\n> ",
+    );
+}
+
+#[test]
+fn test_repl_survives_recursion_limit() {
+    test_repl(
+        concat!(
+            "(define (depth n) (if (= n 0) 0 (+ 1 (depth (- n 1)))))\n",
+            "(depth 2000)\n",
+            "(depth 100000)\n",
+            "(depth 3)\n",
+            ",quit\n",
+        ),
+        "> depth\n> 2000\n> error: recursion too deep\n> 3\n> ",
+    );
+}
+
+#[test]
+fn test_repl_env() {
+    test_repl("(define x 2)\n,env\n,quit\n", "> x\n> x = 2\n> ");
+}
+
+#[test]
+fn test_repl_tokens() {
+    test_repl(
+        "(f 1)\n,tokens\n,quit\n",
+        concat!(
+            "> error: unbound variable: f\n",
+            "> {\"type\":\"Cons\",\"head\":{\"type\":\"Symbol\",\"value\":\"f\"},",
+            "\"tail\":{\"type\":\"Cons\",\"head\":{\"type\":\"Integer\",\"value\":1},",
+            "\"tail\":{\"type\":\"EmptyList\"}}}\n",
+            "> "
+        ),
+    );
+}
+
+#[test]
+fn test_run_gc_stats_on_error() {
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .args(["run", "--gc-stats", "--input", "tests/data/unbound.lisp"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::starts_with("gc: "))
+        .stderr(predicates::str::contains(
+            "error: tests/data/unbound.lisp:3:1: unbound variable: y",
+        ));
+}
+
+#[test]
+fn test_run_gc_stats() {
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .args(["run", "--gc-stats", "--input", "tests/data/fib-call.scheme"])
+        .assert()
+        .success()
+        .stderr(predicates::str::starts_with("gc: "));
+}